@@ -0,0 +1,85 @@
+// Copyright (C) 2026 Daniel Mueller <deso@posteo.net>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::any::TypeId;
+use std::error::Error as StdError;
+use std::fmt::Display;
+use std::fmt::Formatter;
+use std::fmt::Result as FmtResult;
+
+#[cfg(doc)]
+use crate::Id;
+#[cfg(doc)]
+use crate::Ui;
+
+
+/// An error as reported by fallible operations on a [`Ui`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Error {
+  /// The provided [`Id`] does not refer to an existing widget, for
+  /// example because the widget got removed.
+  InvalidId,
+  /// The provided [`Id`] belongs to a different [`Ui`].
+  ForeignUi,
+  /// The data associated with a widget is not of the requested type.
+  DataTypeMismatch {
+    /// The [`TypeId`] of the requested type.
+    expected: TypeId,
+    /// The [`TypeId`] of the widget's actual data.
+    actual: TypeId,
+  },
+  /// The requested operation would make a widget a descendant of
+  /// itself.
+  Cycle,
+  /// The widgets in question do not share the same parent.
+  NotSiblings,
+  /// The widget does not accept messages of the given type.
+  MessageNotAccepted {
+    /// The [`TypeId`] of the message's type.
+    message_type: TypeId,
+  },
+  /// The widget does not answer queries of the given type.
+  QueryNotAccepted {
+    /// The [`TypeId`] of the query's type.
+    query_type: TypeId,
+  },
+  /// The widget is not of the type a registration was created for.
+  WidgetTypeMismatch {
+    /// The [`TypeId`] of the widget type the registration is for.
+    expected: TypeId,
+    /// The [`TypeId`] of the widget's actual type.
+    actual: TypeId,
+  },
+  /// A repeating timer was requested with an interval of zero.
+  ZeroInterval,
+}
+
+impl Display for Error {
+  fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+    match self {
+      Self::InvalidId => write!(f, "The given Id refers to a removed widget"),
+      Self::ForeignUi => write!(f, "The given Id belongs to a different Ui"),
+      Self::DataTypeMismatch { expected, actual } => write!(
+        f,
+        "The widget's data is of type {actual:?} and not the expected {expected:?}"
+      ),
+      Self::Cycle => write!(f, "A widget cannot be a descendant of itself"),
+      Self::NotSiblings => write!(f, "The given widgets do not share the same parent"),
+      Self::MessageNotAccepted { message_type } => write!(
+        f,
+        "The widget does not accept messages of type {message_type:?}"
+      ),
+      Self::QueryNotAccepted { query_type } => write!(
+        f,
+        "The widget does not answer queries of type {query_type:?}"
+      ),
+      Self::WidgetTypeMismatch { expected, actual } => write!(
+        f,
+        "The widget is of type {actual:?} and not the expected {expected:?}"
+      ),
+      Self::ZeroInterval => write!(f, "A repeating timer cannot have an interval of zero"),
+    }
+  }
+}
+
+impl StdError for Error {}
@@ -1,11 +1,17 @@
 // Copyright (C) 2018-2024 Daniel Mueller <deso@posteo.net>
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use std::any::Any;
 use std::fmt::Debug;
 
 use async_trait::async_trait;
 
+use crate::Cap;
+use crate::Id;
 use crate::MutCap;
+use crate::TimerId;
+#[cfg(doc)]
+use crate::Ui;
 
 
 /// A trait representing an object capable of handling events.
@@ -25,6 +31,24 @@ pub trait Handleable<E, M>: Debug {
     Some(event)
   }
 
+  /// Capture an event on its way to the widget it is targeted at.
+  ///
+  /// Before an event passed to [`Ui::handle`] or [`Ui::handle_at`]
+  /// reaches the target widget, i.e., the focused widget or the widget
+  /// under (or grabbing) the pointer, respectively, all of said
+  /// widget's ancestors get a chance to intercept it, starting with the
+  /// root widget (or the root of the active modal scope) and ending
+  /// with the target widget's parent ("capture phase"). Just as with
+  /// [`handle`][Self::handle], a widget can consume the event by
+  /// returning `None`, pass it on unchanged (the default behavior), or
+  /// replace it with a different event. Only once the event made it
+  /// through the capture phase will it be passed to the target widget
+  /// and bubble up from there.
+  #[allow(unused_variables)]
+  async fn capture(&self, cap: &mut dyn MutCap<E, M>, event: E) -> Option<E> {
+    Some(event)
+  }
+
   /// React to a message.
   ///
   /// This method is the handler for the [`MutCap::send`] invocation.
@@ -40,4 +64,50 @@ pub trait Handleable<E, M>: Debug {
   async fn respond(&self, message: &mut M, cap: &mut dyn MutCap<E, M>) -> Option<M> {
     None
   }
+
+  /// React to the widget gaining or losing the input focus.
+  ///
+  /// When the focus moves from one widget to another, the previously
+  /// focused widget is notified about losing the focus before the
+  /// newly focused one is notified about gaining it. Notifications are
+  /// delivered by [`Ui::notify_focus_changes`].
+  #[allow(unused_variables)]
+  async fn focus_changed(&self, cap: &mut dyn MutCap<E, M>, gained: bool) {}
+
+  /// React to the pointer entering or leaving the widget.
+  ///
+  /// Only the innermost widget under the pointer is considered hovered;
+  /// its ancestors are not notified. When the pointer moves from one
+  /// widget to another, the previously hovered widget is notified about
+  /// the pointer leaving before the newly hovered one is notified about
+  /// the pointer entering it. Notifications are delivered by
+  /// [`Ui::pointer_moved`].
+  #[allow(unused_variables)]
+  async fn hover_changed(&self, cap: &mut dyn MutCap<E, M>, entered: bool) {}
+
+  /// Check whether the widget accepts the payload of a drag operation
+  /// started by the widget with the given [`Id`].
+  ///
+  /// When a payload is dropped through [`Ui::drop_at`], the widget at
+  /// the drop point is asked first, followed by its ancestors, until
+  /// one accepts the payload. By default, drops are not accepted.
+  #[allow(unused_variables)]
+  fn accepts_drop(&self, cap: &dyn Cap, source: Id, payload: &dyn Any) -> bool {
+    false
+  }
+
+  /// Receive the payload of a drag operation started by the widget
+  /// with the given [`Id`].
+  ///
+  /// This method is invoked for the widget that accepted the payload
+  /// as per [`accepts_drop`][Self::accepts_drop].
+  #[allow(unused_variables)]
+  async fn dropped(&self, cap: &mut dyn MutCap<E, M>, source: Id, payload: Box<dyn Any>) {}
+
+  /// React to a timer set for the widget firing.
+  ///
+  /// Timers are set by means of [`MutCap::set_timer`] and fire as part
+  /// of [`Ui::fire_timers`].
+  #[allow(unused_variables)]
+  async fn timer_fired(&self, cap: &mut dyn MutCap<E, M>, timer: TimerId) {}
 }
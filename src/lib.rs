@@ -5,10 +5,12 @@
 //! strives for being completely agnostic of the underlying system and
 //! its rendering machinery as well as event dispatching.
 
+mod error;
 mod handleable;
 mod mergeable;
 mod object;
 mod placeholder;
+mod receives;
 mod renderable;
 mod renderer;
 mod ui;
@@ -16,18 +18,31 @@ mod widget;
 
 use self::placeholder::Placeholder;
 
+pub use self::error::Error;
 pub use self::handleable::Handleable;
 pub use self::mergeable::Mergeable;
 pub use self::object::Object;
+pub use self::receives::Query;
+pub use self::receives::Receiver;
+pub use self::receives::Receives;
+pub use self::receives::Responder;
+pub use self::receives::Responds;
 pub use self::renderable::Renderable;
 pub use self::renderer::BBox;
 pub use self::renderer::Renderer;
+pub use self::ui::BroadcastOpts;
 pub use self::ui::Cap;
+pub use self::ui::EventFilterFn;
 pub use self::ui::EventHookFn;
+pub use self::ui::FilterId;
+pub use self::ui::HookId;
 pub use self::ui::Id;
 pub use self::ui::MutCap;
 pub use self::ui::NewDataFn;
 pub use self::ui::NewWidgetFn;
+pub use self::ui::TaskId;
+pub use self::ui::TimerId;
+pub use self::ui::Topic;
 pub use self::ui::Ui;
 pub use self::widget::Widget;
 
@@ -1,6 +1,7 @@
 // Copyright (C) 2018-2025 Daniel Mueller <deso@posteo.net>
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use std::any::Any;
 use std::any::TypeId;
 
 use async_trait::async_trait;
@@ -13,6 +14,7 @@ use crate::MutCap;
 use crate::Object;
 use crate::Renderable;
 use crate::Renderer;
+use crate::TimerId;
 #[cfg(doc)]
 use crate::Ui;
 use crate::Widget;
@@ -46,6 +48,10 @@ impl<E, M> Handleable<E, M> for Placeholder {
     unreachable!()
   }
 
+  async fn capture(&self, _cap: &mut dyn MutCap<E, M>, _event: E) -> Option<E> {
+    unreachable!()
+  }
+
   async fn react(&self, _message: M, _cap: &mut dyn MutCap<E, M>) -> Option<M> {
     unreachable!()
   }
@@ -53,6 +59,26 @@ impl<E, M> Handleable<E, M> for Placeholder {
   async fn respond(&self, _message: &mut M, _cap: &mut dyn MutCap<E, M>) -> Option<M> {
     unreachable!()
   }
+
+  async fn focus_changed(&self, _cap: &mut dyn MutCap<E, M>, _gained: bool) {
+    unreachable!()
+  }
+
+  async fn hover_changed(&self, _cap: &mut dyn MutCap<E, M>, _entered: bool) {
+    unreachable!()
+  }
+
+  fn accepts_drop(&self, _cap: &dyn Cap, _source: Id, _payload: &dyn Any) -> bool {
+    unreachable!()
+  }
+
+  async fn dropped(&self, _cap: &mut dyn MutCap<E, M>, _source: Id, _payload: Box<dyn Any>) {
+    unreachable!()
+  }
+
+  async fn timer_fired(&self, _cap: &mut dyn MutCap<E, M>, _timer: TimerId) {
+    unreachable!()
+  }
 }
 
 impl<E, M> Widget<E, M> for Placeholder {
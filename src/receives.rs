@@ -0,0 +1,234 @@
+// Copyright (C) 2026 Daniel Mueller <deso@posteo.net>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+use std::any::Any;
+use std::any::TypeId;
+use std::fmt::Debug;
+use std::fmt::Formatter;
+use std::fmt::Result as FmtResult;
+use std::future::Future;
+use std::pin::Pin;
+
+use async_trait::async_trait;
+
+use crate::MutCap;
+#[cfg(doc)]
+use crate::Ui;
+use crate::Widget;
+
+
+/// The prototype of a type-erased function dispatching a message to a
+/// widget.
+type ReceiveFn<E, M> = for<'f> fn(
+  &'f dyn Widget<E, M>,
+  &'f mut dyn MutCap<E, M>,
+  Box<dyn Any>,
+) -> Pin<Box<dyn Future<Output = Option<Box<dyn Any>>> + 'f>>;
+/// The prototype of a type-erased function dispatching a query to a
+/// widget.
+type RespondFn<E, M> = for<'f> fn(
+  &'f dyn Widget<E, M>,
+  &'f mut dyn MutCap<E, M>,
+  Box<dyn Any>,
+) -> Pin<Box<dyn Future<Output = Box<dyn Any>> + 'f>>;
+
+
+/// A trait for widgets able to receive messages of type `T`.
+///
+/// In addition to messages of the type `M` shared by all widgets of a
+/// [`Ui`], widgets may receive messages of arbitrary other types. To
+/// do so, they implement this trait for every such type and register a
+/// corresponding [`Receiver`] with the `Ui` by means of
+/// [`MutCap::add_receiver`]. Messages are then sent using
+/// [`Ui::send_typed`] or its counterpart on `dyn MutCap`.
+#[async_trait(?Send)]
+pub trait Receives<T, E, M>: Widget<E, M>
+where
+  T: 'static,
+{
+  /// Receive a message of type `T`.
+  ///
+  /// Just like [`Handleable::react`][crate::Handleable::react], the
+  /// widget may return a message in response.
+  async fn receive(&self, cap: &mut dyn MutCap<E, M>, message: T) -> Option<T>;
+}
+
+
+/// A registration of a widget's [`Receives`] implementation for a
+/// specific message type.
+pub struct Receiver<E, M> {
+  /// The `TypeId` of the widget type the receiver was created for.
+  widget_type: TypeId,
+  /// The `TypeId` of the message type the receiver was created for.
+  message_type: TypeId,
+  /// The function dispatching messages to the widget.
+  receive_fn: ReceiveFn<E, M>,
+}
+
+impl<E, M> Receiver<E, M>
+where
+  E: 'static,
+  M: 'static,
+{
+  /// Create a `Receiver` dispatching messages of type `T` to widgets of
+  /// type `W`.
+  pub fn new<W, T>() -> Self
+  where
+    W: Receives<T, E, M> + 'static,
+    T: 'static,
+  {
+    fn receive<'f, W, T, E, M>(
+      widget: &'f dyn Widget<E, M>,
+      cap: &'f mut dyn MutCap<E, M>,
+      message: Box<dyn Any>,
+    ) -> Pin<Box<dyn Future<Output = Option<Box<dyn Any>>> + 'f>>
+    where
+      W: Receives<T, E, M> + 'static,
+      T: 'static,
+      E: 'static,
+      M: 'static,
+    {
+      // The `Ui` checks the widget and message types before dispatching.
+      let widget = widget.downcast_ref::<W>().unwrap();
+      let message = message.downcast::<T>().unwrap();
+
+      Box::pin(async move {
+        let response = widget.receive(cap, *message).await;
+        response.map(|response| Box::new(response) as Box<dyn Any>)
+      })
+    }
+
+    Self {
+      widget_type: TypeId::of::<W>(),
+      message_type: TypeId::of::<T>(),
+      receive_fn: receive::<W, T, E, M>,
+    }
+  }
+
+  /// Retrieve the `TypeId` of the widget type the receiver is for.
+  pub(crate) fn widget_type(&self) -> TypeId {
+    self.widget_type
+  }
+
+  /// Retrieve the `TypeId` of the message type the receiver is for.
+  pub(crate) fn message_type(&self) -> TypeId {
+    self.message_type
+  }
+
+  /// Retrieve the function dispatching messages to the widget.
+  pub(crate) fn receive_fn(&self) -> ReceiveFn<E, M> {
+    self.receive_fn
+  }
+}
+
+impl<E, M> Debug for Receiver<E, M> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+    f.debug_struct("Receiver")
+      .field("widget_type", &self.widget_type)
+      .field("message_type", &self.message_type)
+      .finish()
+  }
+}
+
+
+/// A trait for queries, i.e., messages that are answered with a reply
+/// of a type specific to the query.
+pub trait Query: 'static {
+  /// The type of the reply to the query.
+  type Reply: 'static;
+}
+
+
+/// A trait for widgets able to answer queries of type `Q`.
+///
+/// This trait is the typed counterpart to
+/// [`Handleable::respond`][crate::Handleable::respond]. Just as with
+/// [`Receives`], widgets implement it for every query type they answer
+/// and register a corresponding [`Responder`] with the [`Ui`] by means
+/// of [`MutCap::add_responder`]. Queries are then sent using
+/// [`Ui::request`] or its counterpart on `dyn MutCap`.
+#[async_trait(?Send)]
+pub trait Responds<Q, E, M>: Widget<E, M>
+where
+  Q: Query,
+{
+  /// Answer a query of type `Q`.
+  async fn respond_to(&self, cap: &mut dyn MutCap<E, M>, query: Q) -> Q::Reply;
+}
+
+
+/// A registration of a widget's [`Responds`] implementation for a
+/// specific query type.
+pub struct Responder<E, M> {
+  /// The `TypeId` of the widget type the responder was created for.
+  widget_type: TypeId,
+  /// The `TypeId` of the query type the responder was created for.
+  query_type: TypeId,
+  /// The function dispatching queries to the widget.
+  respond_fn: RespondFn<E, M>,
+}
+
+impl<E, M> Responder<E, M>
+where
+  E: 'static,
+  M: 'static,
+{
+  /// Create a `Responder` dispatching queries of type `Q` to widgets of
+  /// type `W`.
+  pub fn new<W, Q>() -> Self
+  where
+    W: Responds<Q, E, M> + 'static,
+    Q: Query,
+  {
+    fn respond<'f, W, Q, E, M>(
+      widget: &'f dyn Widget<E, M>,
+      cap: &'f mut dyn MutCap<E, M>,
+      query: Box<dyn Any>,
+    ) -> Pin<Box<dyn Future<Output = Box<dyn Any>> + 'f>>
+    where
+      W: Responds<Q, E, M> + 'static,
+      Q: Query,
+      E: 'static,
+      M: 'static,
+    {
+      // The `Ui` checks the widget and query types before dispatching.
+      let widget = widget.downcast_ref::<W>().unwrap();
+      let query = query.downcast::<Q>().unwrap();
+
+      Box::pin(async move {
+        let reply = widget.respond_to(cap, *query).await;
+        Box::new(reply) as Box<dyn Any>
+      })
+    }
+
+    Self {
+      widget_type: TypeId::of::<W>(),
+      query_type: TypeId::of::<Q>(),
+      respond_fn: respond::<W, Q, E, M>,
+    }
+  }
+
+  /// Retrieve the `TypeId` of the widget type the responder is for.
+  pub(crate) fn widget_type(&self) -> TypeId {
+    self.widget_type
+  }
+
+  /// Retrieve the `TypeId` of the query type the responder is for.
+  pub(crate) fn query_type(&self) -> TypeId {
+    self.query_type
+  }
+
+  /// Retrieve the function dispatching queries to the widget.
+  pub(crate) fn respond_fn(&self) -> RespondFn<E, M> {
+    self.respond_fn
+  }
+}
+
+impl<E, M> Debug for Responder<E, M> {
+  fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+    f.debug_struct("Responder")
+      .field("widget_type", &self.widget_type)
+      .field("query_type", &self.query_type)
+      .finish()
+  }
+}
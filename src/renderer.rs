@@ -22,6 +22,21 @@ pub struct BBox {
   pub h: u16,
 }
 
+impl BBox {
+  /// Check whether the bounding box contains the given point.
+  pub fn contains(&self, x: u16, y: u16) -> bool {
+    let x = u32::from(x);
+    let y = u32::from(y);
+    let x_min = u32::from(self.x);
+    let y_min = u32::from(self.y);
+
+    x >= x_min
+      && x < x_min + u32::from(self.w)
+      && y >= y_min
+      && y < y_min + u32::from(self.h)
+  }
+}
+
 
 /// An abstraction for objects used for rendering widgets.
 pub trait Renderer {
@@ -2,29 +2,48 @@
 // SPDX-License-Identifier: GPL-3.0-or-later
 
 use std::any::Any;
+use std::any::TypeId;
+use std::borrow::Cow;
+use std::cell::Cell;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::collections::VecDeque;
 use std::fmt::Debug;
 use std::fmt::Display;
 use std::fmt::Formatter;
 use std::fmt::Result as FmtResult;
+use std::future::poll_fn;
 use std::future::Future;
-use std::mem::replace;
 use std::ops::Deref;
+use std::ops::Index as IndexOp;
+use std::ops::IndexMut;
 use std::pin::Pin;
 use std::rc::Rc;
 use std::slice::Iter;
-#[cfg(debug_assertions)]
+use std::sync::atomic::AtomicBool;
 use std::sync::atomic::AtomicUsize;
-#[cfg(debug_assertions)]
 use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::task::Context;
+use std::task::Poll;
+use std::task::Wake;
+use std::task::Waker;
+use std::time::Duration;
+use std::time::Instant;
 
 use async_trait::async_trait;
 
 use crate::BBox;
+use crate::Error;
 #[cfg(doc)]
 use crate::Handleable;
 use crate::Mergeable;
 use crate::Placeholder;
+use crate::Query;
+use crate::Receiver;
 use crate::Renderer;
+use crate::Responder;
 use crate::Widget;
 
 
@@ -62,21 +81,210 @@ impl Display for Index {
 }
 
 
+/// An entry in the list of installed event hooks.
+///
+/// Entries are ordered by descending priority and, for equal
+/// priorities, by ascending registration sequence number, which is the
+/// order in which hooks are invoked.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+struct HookEntry {
+  /// The hook's priority, reversed so that higher priorities sort first.
+  priority: Reverse<i32>,
+  /// The hook's registration sequence number.
+  sequence: usize,
+  /// The index of the widget that installed the hook.
+  idx: Index,
+}
+
+
+/// An entry in the list of installed event filters.
+///
+/// Entries are ordered by ascending registration sequence number, which
+/// is the order in which filters are invoked.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+struct FilterEntry {
+  /// The filter's registration sequence number.
+  sequence: usize,
+  /// The index of the widget that installed the filter.
+  idx: Index,
+}
+
+
+/// Options controlling the delivery of a message passed to
+/// [`MutCap::broadcast`].
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct BroadcastOpts {
+  /// Whether to skip widgets that are not displayed.
+  pub skip_hidden: bool,
+  /// Whether to stop delivery once a widget consumed the message.
+  pub stop_on_consume: bool,
+}
+
+
+/// The key identifying a [`Topic`].
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+enum TopicKey {
+  /// A topic identified by name.
+  Named(Cow<'static, str>),
+  /// A topic identified by a type.
+  Typed(TypeId),
+}
+
+
+/// A topic widgets can subscribe to and messages can be published on.
+///
+/// Topics are identified either by name or by a type. See
+/// [`MutCap::publish`] for details.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Topic {
+  key: TopicKey,
+}
+
+impl Topic {
+  /// Create a `Topic` identified by the given name.
+  pub fn named<S>(name: S) -> Self
+  where
+    S: Into<Cow<'static, str>>,
+  {
+    Self {
+      key: TopicKey::Named(name.into()),
+    }
+  }
+
+  /// Create a `Topic` identified by the type `T`.
+  pub fn of<T>() -> Self
+  where
+    T: ?Sized + 'static,
+  {
+    Self {
+      key: TopicKey::Typed(TypeId::of::<T>()),
+    }
+  }
+}
+
+
+/// A handle to an installed event hook.
+///
+/// A `HookId` is handed out when installing an event hook and can be
+/// used to uninstall this very hook later on.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct HookId {
+  /// The `Id` of the widget that installed the hook.
+  widget: Id,
+  /// The hook's registration sequence number.
+  sequence: usize,
+}
+
+
+/// A handle to a timer.
+///
+/// A `TimerId` is handed out when setting a timer. It is passed to the
+/// widget when the timer fires and can be used to cancel the timer.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct TimerId {
+  /// The `Id` of the widget the timer belongs to.
+  widget: Id,
+  /// The timer's sequence number.
+  sequence: usize,
+}
+
+
+/// A timer set by [`MutCap::set_timer`].
+#[derive(Debug)]
+struct Timer {
+  /// The handle identifying the timer.
+  id: TimerId,
+  /// The point in time at which the timer fires next, or `None` if
+  /// it lies too far in the future to be represented.
+  deadline: Option<Instant>,
+  /// The interval at which the timer fires, if it is repeating.
+  interval: Option<Duration>,
+}
+
+
+/// A handle to a task.
+///
+/// A `TaskId` is handed out when spawning a task and can be used to
+/// cancel the task.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct TaskId {
+  /// The `Id` of the widget the task belongs to.
+  widget: Id,
+  /// The task's sequence number.
+  sequence: usize,
+}
+
+
+/// The waker of a task spawned by [`MutCap::spawn`].
+#[derive(Debug)]
+struct TaskWaker {
+  /// Whether the task has been woken and is due for being polled.
+  woken: AtomicBool,
+  /// The waker of the [`Ui::run_until`] invocation driving the task,
+  /// if any.
+  driver: Arc<Mutex<Option<Waker>>>,
+}
+
+impl Wake for TaskWaker {
+  fn wake(self: Arc<Self>) {
+    self.wake_by_ref()
+  }
+
+  fn wake_by_ref(self: &Arc<Self>) {
+    let () = self.woken.store(true, Ordering::Release);
+    // Wake the driver only after releasing the lock, as its waker may
+    // in turn wake tasks synchronously.
+    let driver = self.driver.lock().unwrap().clone();
+    if let Some(driver) = driver {
+      driver.wake()
+    }
+  }
+}
+
+
+/// A task spawned by [`MutCap::spawn`].
+struct Task<M> {
+  /// The handle identifying the task.
+  id: TaskId,
+  /// The future driving the task.
+  future: Pin<Box<dyn Future<Output = M>>>,
+  /// The task's waker.
+  waker: Arc<TaskWaker>,
+}
+
+
+/// A handle to an installed event filter.
+///
+/// A `FilterId` is handed out when installing an event filter and can
+/// be used to uninstall this very filter later on.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct FilterId {
+  /// The `Id` of the widget that installed the filter.
+  widget: Id,
+  /// The filter's registration sequence number.
+  sequence: usize,
+}
+
+
 /// An `Id` uniquely representing a widget.
+///
+/// Once a widget got removed, the slot it occupied may be reused by a
+/// newly created widget. The generation an `Id` carries along
+/// distinguishes between the two, making sure that a stale `Id` is not
+/// mistaken for the `Id` of the new widget.
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub struct Id {
-  #[cfg(debug_assertions)]
   ui_id: usize,
   idx: Index,
+  generation: usize,
 }
 
 impl Id {
-  #[allow(unused_variables)]
-  fn new<E, M>(idx: usize, ui: &Ui<E, M>) -> Id {
+  fn new<E, M>(idx: Index, ui: &Ui<E, M>) -> Id {
     Self {
-      #[cfg(debug_assertions)]
       ui_id: ui.id,
-      idx: Index::new(idx),
+      idx,
+      generation: ui.widgets.slots[idx.idx].generation,
     }
   }
 }
@@ -137,29 +345,41 @@ where
   ) -> Option<E> {
     let mut result = None;
 
-    for idx in Rc::clone(&ui.hooked).as_ref() {
-      let data = &mut ui.widgets[idx.idx];
-      match data.0.event_hook {
-        EventHook::Invoked(D(hook_fn)) => {
-          let widget = Rc::clone(&data.1);
-          let event = hook_fn(widget.as_ref(), ui, event).await;
-
-          result = merge(result, event);
-        },
-        EventHook::Installed(D(hook_fn)) => {
-          data.0.event_hook = EventHook::Invoked(D(hook_fn));
-
-          if event.is_some() {
-            let widget = Rc::clone(&data.1);
-            let event = hook_fn(widget.as_ref(), ui, event).await;
+    for entry in Rc::clone(&ui.hooked).as_ref() {
+      // A previously invoked hook may have removed the widget or
+      // replaced its hook.
+      if ui.hooked.binary_search(entry).is_err() {
+        continue
+      }
 
-            result = merge(result, event);
+      let data = &mut ui.widgets[entry.idx];
+      let hook = data
+        .0
+        .event_hooks
+        .iter_mut()
+        .find(|(sequence, _)| *sequence == entry.sequence);
+
+      let hook_fn = match hook {
+        Some((_, EventHook::Invoked(D(hook_fn)))) => Rc::clone(hook_fn),
+        Some((_, hook @ EventHook::Installed(..))) => {
+          let hook_fn = Rc::clone(hook.hook_fn());
+          *hook = EventHook::Invoked(D(Rc::clone(&hook_fn)));
+
+          if event.is_none() {
+            continue
           }
+          hook_fn
         },
-        EventHook::None => {
-          debug_assert!(false, "Widget registered as hooked but no hook func found")
+        None => {
+          debug_assert!(false, "Hook registered but no hook func found");
+          continue
         },
       };
+
+      let widget = Rc::clone(&data.1);
+      let event = hook_fn(widget.as_ref(), ui, event).await;
+
+      result = merge(result, event);
     }
     merge(merge(pre_hook_event, unhandled), result)
   }
@@ -191,11 +411,18 @@ pub type NewDataFn = dyn FnOnce() -> Box<dyn Any>;
 pub type NewWidgetFn<E, M> = dyn FnOnce(Id, &mut dyn MutCap<E, M>) -> Box<dyn Widget<E, M>>;
 /// The prototype of an event hook function.
 pub type EventHookFn<E, M> =
-  &'static dyn for<'f> Fn(
+  Rc<dyn for<'f> Fn(
     &'f dyn Widget<E, M>,
     &'f mut dyn MutCap<E, M>,
     Option<&'f E>,
-  ) -> Pin<Box<dyn Future<Output = Option<E>> + 'f>>;
+  ) -> Pin<Box<dyn Future<Output = Option<E>> + 'f>>>;
+/// The prototype of an event filter function.
+pub type EventFilterFn<E, M> =
+  Rc<dyn for<'f> Fn(
+    &'f dyn Widget<E, M>,
+    &'f mut dyn MutCap<E, M>,
+    E,
+  ) -> Pin<Box<dyn Future<Output = Option<E>> + 'f>>>;
 
 mod private {
   pub trait Sealed {}
@@ -206,16 +433,28 @@ pub trait Cap: Debug + private::Sealed {
   /// Retrieve a reference to a widget's data.
   fn data(&self, widget: Id) -> &dyn Any;
 
+  /// Retrieve a reference to a widget's data, reporting an error if
+  /// the provided [`Id`] is invalid.
+  fn try_data(&self, widget: Id) -> Result<&dyn Any, Error>;
+
   /// Retrieve an iterator over the children. Iteration happens in
   /// z-order, from highest to lowest.
   fn children(&self, widget: Id) -> ChildIter<'_>;
 
+  /// Retrieve an iterator over the children, reporting an error if the
+  /// provided [`Id`] is invalid.
+  fn try_children(&self, widget: Id) -> Result<ChildIter<'_>, Error>;
+
   /// Retrieve the [`Id`] of the root widget.
   fn root_id(&self) -> Id;
 
   /// Retrieve the parent of the given widget.
   fn parent_id(&self, widget: Id) -> Option<Id>;
 
+  /// Retrieve the parent of the given widget, reporting an error if the
+  /// provided [`Id`] is invalid.
+  fn try_parent_id(&self, widget: Id) -> Result<Option<Id>, Error>;
+
   /// Check whether a widget has its visibility flag set.
   ///
   /// Note that a return value of `true` does not necessary mean that
@@ -237,6 +476,38 @@ pub trait Cap: Debug + private::Sealed {
 
   /// Check whether the widget with the given [`Id`] is focused.
   fn is_focused(&self, widget: Id) -> bool;
+
+  /// Retrieve the widget currently under the pointer, if any.
+  ///
+  /// The hovered widget is updated by [`Ui::pointer_moved`]. A widget
+  /// that is no longer displayed is no longer considered hovered. It
+  /// is notified about the pointer leaving it the next time
+  /// notifications are delivered, e.g., as part of [`Ui::handle`] or
+  /// [`Ui::pointer_moved`].
+  fn hovered(&self) -> Option<Id>;
+
+  /// Retrieve the widget currently grabbing the pointer, if any.
+  ///
+  /// See [`MutCap::grab_pointer`].
+  fn pointer_grab(&self) -> Option<Id>;
+
+  /// Retrieve the widget that started the ongoing drag operation, if
+  /// any.
+  ///
+  /// See [`MutCap::start_drag`].
+  fn drag_source(&self) -> Option<Id>;
+
+  /// Retrieve the root of the active modal scope, if any.
+  ///
+  /// Renderers may use this information for, say, dimming everything
+  /// outside of the modal scope.
+  fn modal_root(&self) -> Option<Id>;
+
+  /// Check whether a widget takes part in focus traversal.
+  fn is_focusable(&self, widget: Id) -> bool;
+
+  /// Retrieve a widget's tab index, if any.
+  fn tab_index(&self, widget: Id) -> Option<u32>;
 }
 
 
@@ -246,6 +517,10 @@ pub trait MutCap<E, M>: Cap + Deref<Target = dyn Cap> {
   /// Retrieve a mutable reference to a widget's data.
   fn data_mut(&mut self, widget: Id) -> &mut dyn Any;
 
+  /// Retrieve a mutable reference to a widget's data, reporting an
+  /// error if the provided [`Id`] is invalid.
+  fn try_data_mut(&mut self, widget: Id) -> Result<&mut dyn Any, Error>;
+
   /// Add a widget to the `Ui` represented by the capability.
   // TODO: We should not require a Box here conceptually, but omitting
   //       it will require the unboxed closures feature to stabilize.
@@ -256,6 +531,68 @@ pub trait MutCap<E, M>: Cap + Deref<Target = dyn Cap> {
     new_widget: Box<NewWidgetFn<E, M>>,
   ) -> Id;
 
+  /// Remove a widget along with all its children from the `Ui`.
+  ///
+  /// The widget is detached from its parent and its data as well as
+  /// the `Ui`'s reference to the widget itself are dropped. Should the
+  /// focused widget be part of the removed subtree, the focus is
+  /// returned to the most recently focused widget that is still
+  /// displayed, if any. Event hooks installed by any of the removed
+  /// widgets are unregistered.
+  ///
+  /// Subsequent usage of the `Id` of any of the removed widgets is a
+  /// programming error and will cause a panic, even if a newly created
+  /// widget ended up reusing the removed widget's storage.
+  ///
+  /// # Panics
+  ///
+  /// This method panics when attempting to remove the root widget.
+  fn remove_widget(&mut self, widget: Id);
+
+  /// Move a widget along with all its children to a new parent.
+  ///
+  /// The widget is inserted into the new parent's list of children at
+  /// the given z-order `position`, with zero being the top-most spot.
+  /// A position past the end places the widget at the bottom.
+  ///
+  /// If the focused widget is part of the moved subtree, it stays
+  /// focused as long as it is still displayed and part of the active
+  /// modal scope (if any) at its new location (in which case it is
+  /// reordered as if it got focused anew). Otherwise, the focus is
+  /// returned to the most recently focused widget that is still
+  /// displayed and in scope, if any.
+  ///
+  /// Moving a widget below itself or one of its descendants is
+  /// rejected with [`Error::Cycle`].
+  fn set_parent(&mut self, widget: Id, new_parent: Id, position: usize) -> Result<(), Error>;
+
+  /// Add a widget to the `Ui` represented by the capability, inserting
+  /// it at the given z-order `position` among its siblings.
+  ///
+  /// A position of zero makes the widget the top-most child of
+  /// `parent`. A position past the end places it at the bottom, which
+  /// is what [`add_widget`][Self::add_widget] does.
+  fn add_widget_at(
+    &mut self,
+    parent: Id,
+    position: usize,
+    new_data: Box<NewDataFn>,
+    new_widget: Box<NewWidgetFn<E, M>>,
+  ) -> Id;
+
+  /// Raise a widget to the top of its siblings' z-order.
+  fn raise(&mut self, widget: Id);
+
+  /// Lower a widget to the bottom of its siblings' z-order.
+  fn lower(&mut self, widget: Id);
+
+  /// Reorder a widget to reside directly above the given sibling in
+  /// z-order.
+  ///
+  /// An error is reported if the two widgets do not share the same
+  /// parent.
+  fn raise_above(&mut self, widget: Id, sibling: Id) -> Result<(), Error>;
+
   /// Show a widget, i.e., set its and its parents' visibility flag.
   ///
   /// This method sets the referenced widget's visibility flag as well
@@ -267,6 +604,11 @@ pub trait MutCap<E, M>: Cap + Deref<Target = dyn Cap> {
   /// This method makes sure that widget referenced is no longer
   /// displayed. If the widget has children, all those children will
   /// also be hidden.
+  ///
+  /// If the focused widget is no longer displayed as a result, the
+  /// focus is returned to the most recently focused widget that is
+  /// still displayed, if any. Similarly, a pointer grab held by a
+  /// widget no longer displayed is released.
   fn hide(&mut self, widget: Id);
 
   /// Focus a widget.
@@ -274,10 +616,112 @@ pub trait MutCap<E, M>: Cap + Deref<Target = dyn Cap> {
   /// The focused widget is the one receiving certain types of events
   /// (such as key events) first but may also be rendered in a different
   /// color or be otherwise highlighted. Note that being focused implies
-  /// being visible. This invariant is enforced internally.
+  /// being visible. This invariant is enforced internally. Focusing a
+  /// widget furthermore raises it and all its parents to the top of
+  /// their siblings' z-order.
+  ///
+  /// While a modal scope is active, requests to focus a widget outside
+  /// of it are ignored. See [`push_modal`][Self::push_modal].
+  ///
+  /// The previously focused widget as well as the newly focused one
+  /// are informed about the change via
+  /// [`Handleable::focus_changed`]. Refer to
+  /// [`Ui::notify_focus_changes`] for details on when that happens.
   fn focus(&mut self, widget: Id);
 
-  /// Install or remove an event hook handler.
+  /// Make the given widget the root of a new modal scope.
+  ///
+  /// While a modal scope is active, the focus is confined to the
+  /// scope's root widget and its descendants: requests to focus a
+  /// widget outside of it are ignored and events bubbling up from the
+  /// focused widget do not travel past the scope's root. If the focused
+  /// widget is not part of the new scope, the scope's root widget gets
+  /// focused.
+  ///
+  /// Modal scopes can be nested, with only the most recently pushed one
+  /// being active. The root of a new scope does not have to be part of
+  /// the currently active one: the new scope takes its place until it
+  /// is popped again.
+  fn push_modal(&mut self, widget: Id);
+
+  /// Deactivate the most recently pushed modal scope, returning the
+  /// `Id` of its root widget.
+  ///
+  /// If no widget is focused or the focused widget is not displayed or
+  /// not part of the now active modal scope, the focus is returned to
+  /// the most recently focused widget that is displayed and part of
+  /// that scope, if any.
+  fn pop_modal(&mut self) -> Option<Id>;
+
+  /// Route all pointer events to the given widget.
+  ///
+  /// While the pointer is grabbed, events passed to [`Ui::handle_at`]
+  /// are dispatched to the grabbing widget instead of the widget at the
+  /// given point, and [`Ui::pointer_moved`] considers the grabbing
+  /// widget to be hovered. That is useful for drag interactions, where
+  /// the pointer may leave the dragged widget's area temporarily. Only
+  /// a single widget can grab the pointer at any time; a new grab
+  /// replaces the existing one.
+  ///
+  /// The grab lasts until [`release_pointer`][Self::release_pointer]
+  /// is invoked or until the grabbing widget is no longer displayed,
+  /// e.g., because it or one of its ancestors got hidden or it got
+  /// moved below a hidden widget, or is removed. Consequently, a
+  /// request to grab the pointer on behalf of a widget that is not
+  /// displayed is ignored. A grab held by a widget outside of the
+  /// active modal scope is ineffective.
+  fn grab_pointer(&mut self, widget: Id);
+
+  /// Release the pointer grab, if any.
+  fn release_pointer(&mut self);
+
+  /// Start a drag operation on behalf of the given widget.
+  ///
+  /// The payload is handed to the widget accepting it once it is
+  /// dropped via [`Ui::drop_at`]. Refer to
+  /// [`Handleable::accepts_drop`] and [`Handleable::dropped`] for
+  /// the protocol used for negotiating the drop. An ongoing drag
+  /// operation is canceled and its payload dropped. The drag operation
+  /// is canceled as well if the source widget is removed.
+  fn start_drag(&mut self, source: Id, payload: Box<dyn Any>);
+
+  /// Cancel the ongoing drag operation, if any, returning its payload.
+  fn cancel_drag(&mut self) -> Option<Box<dyn Any>>;
+
+  /// Set whether a widget takes part in focus traversal.
+  ///
+  /// Widgets are focusable by default. Note that this flag only
+  /// affects [`focus_next`][Self::focus_next] and
+  /// [`focus_prev`][Self::focus_prev]; any widget can still be
+  /// focused explicitly.
+  fn set_focusable(&mut self, widget: Id, focusable: bool);
+
+  /// Set or clear a widget's tab index.
+  ///
+  /// Focus traversal visits widgets with a tab index first, in
+  /// ascending order, followed by all remaining widgets in document
+  /// order.
+  fn set_tab_index(&mut self, widget: Id, tab_index: Option<u32>);
+
+  /// Focus the next widget in the focus chain.
+  ///
+  /// The focus chain contains all displayed and focusable widgets. It
+  /// is ordered by tab index first and by document order after that,
+  /// where document order is that of a pre-order traversal of the
+  /// widget tree, with siblings being ordered by creation. Traversal
+  /// wraps around at the end of the chain. If the focused widget is
+  /// not part of the chain, the first widget of the chain gets focused.
+  fn focus_next(&mut self);
+
+  /// Focus the previous widget in the focus chain.
+  ///
+  /// This method is the counterpart to
+  /// [`focus_next`][Self::focus_next], wrapping around at the start of
+  /// the chain. If the focused widget is not part of the chain, the
+  /// last widget of the chain gets focused.
+  fn focus_prev(&mut self);
+
+  /// Install an event hook handler.
   ///
   /// The event hook handler is a call back function that is invoked for
   /// all events originating outside of the UI, i.e., those that come in
@@ -293,36 +737,255 @@ pub trait MutCap<E, M>: Cap + Deref<Target = dyn Cap> {
   ///
   /// Event hook handlers are allowed to emit an event on their own,
   /// just as "normal" event handlers. The events of all hooks get
-  /// merged into a single event. As such, they must be mergeable. Note
-  /// that the order in which multiple event hooks are invoked relative
-  /// to each other is unspecified, and that should be taken into
-  /// account when providing a `Mergeable` implementation for the
-  /// provided event type.
+  /// merged into a single event. As such, they must be mergeable.
+  /// Hooks installed through this method have a priority of zero; see
+  /// [`hook_events_with_priority`][Self::hook_events_with_priority]
+  /// for details on the order in which hooks are invoked and their
+  /// events merged.
   /// Furthermore, the final merged event is not passed to widgets, but
   /// returned straight back.
   ///
   /// Note that event hook functions are only able to inspect events and
-  /// not change or discard them.
+  /// not change or discard them. Use
+  /// [`filter_events`][Self::filter_events] for that purpose.
+  ///
+  /// A widget (identified by the given `Id`) may install any number of
+  /// hook handlers, which may capture arbitrary state. The method
+  /// returns a [`HookId`] that can be passed to
+  /// [`unhook_events`][Self::unhook_events] for uninstalling the hook
+  /// again. All hooks of a widget are uninstalled automatically when
+  /// the widget is removed.
+  fn hook_events(&mut self, widget: Id, hook_fn: EventHookFn<E, M>) -> HookId
+  where
+    E: Mergeable;
+
+  /// Install an event hook handler with the given priority.
   ///
-  /// A widget (identified by the given `Id`) may only register one
-  /// handler and subsequent requests will overwrite the previously
-  /// installed one. The method returns the handler that was previously
-  /// installed, if any.
-  fn hook_events(
+  /// This method behaves just like [`hook_events`][Self::hook_events],
+  /// but allows for specifying the priority of the hook. Hooks are
+  /// invoked in order of descending priority, with hooks of equal
+  /// priority being invoked in the order in which they got installed.
+  /// Events emitted by hooks are merged in the same order, i.e., the
+  /// event of a hook invoked earlier is the one that events of later
+  /// hooks get merged into.
+  fn hook_events_with_priority(
     &mut self,
     widget: Id,
-    hook_fn: Option<EventHookFn<E, M>>,
-  ) -> Option<EventHookFn<E, M>>
+    hook_fn: EventHookFn<E, M>,
+    priority: i32,
+  ) -> HookId
   where
     E: Mergeable;
 
+  /// Uninstall a previously installed event hook handler.
+  ///
+  /// The method returns the hook handler that got uninstalled or `None`
+  /// if the hook is no longer installed, e.g., because it got
+  /// uninstalled before or because the widget that installed it has
+  /// been removed.
+  fn unhook_events(&mut self, hook: HookId) -> Option<EventHookFn<E, M>>;
+
+  /// Install an event filter.
+  ///
+  /// An event filter is a call back function that, similar to an event
+  /// hook (see [`hook_events`][Self::hook_events]), is invoked for all
+  /// events coming in through the [`Ui::handle`] method. Unlike event
+  /// hooks, filters receive the event by value and decide how event
+  /// dispatch is to continue: by returning the event, possibly after
+  /// changing it or replacing it with a different one altogether, or
+  /// by returning `None`, in which case the event is discarded.
+  ///
+  /// Filters run before event hooks and before any widget sees the
+  /// event, in the order in which they got installed, with each filter
+  /// being passed the event as returned by the previous one. Once a
+  /// filter discarded the event, no further filters, hooks, or widgets
+  /// are invoked for it.
+  ///
+  /// A widget (identified by the given `Id`) may install any number of
+  /// filters, which may capture arbitrary state. The method returns a
+  /// [`FilterId`] that can be passed to
+  /// [`unfilter_events`][Self::unfilter_events] for uninstalling the
+  /// filter again. All filters of a widget are uninstalled
+  /// automatically when the widget is removed.
+  fn filter_events(&mut self, widget: Id, filter_fn: EventFilterFn<E, M>) -> FilterId;
+
+  /// Uninstall a previously installed event filter.
+  ///
+  /// The method returns the filter that got uninstalled or `None` if
+  /// the filter is no longer installed, e.g., because it got
+  /// uninstalled before or because the widget that installed it has
+  /// been removed.
+  fn unfilter_events(&mut self, filter: FilterId) -> Option<EventFilterFn<E, M>>;
+
   /// Send the provided message to the given widget.
   async fn send(&mut self, widget: Id, message: M) -> Option<M>;
 
+  /// Send the provided message to the given widget, reporting an error
+  /// if the provided [`Id`] is invalid.
+  async fn try_send(&mut self, widget: Id, message: M) -> Result<Option<M>, Error>;
+
   /// Send the provided message to the given widget, without
   /// transferring ownership of the message.
   async fn call(&mut self, widget: Id, message: &mut M) -> Option<M>;
 
+  /// Register a receiver for messages of a type other than `M` with
+  /// the given widget.
+  ///
+  /// The [`Receiver`] has to have been created for the widget's type,
+  /// or [`Error::WidgetTypeMismatch`] is reported. Note that while the
+  /// widget is still being constructed, i.e., from within its
+  /// [`NewWidgetFn`], its type cannot be checked yet. A receiver
+  /// registered previously for the same message type is replaced. See
+  /// [`Receives`][crate::Receives] for details.
+  fn add_receiver(&mut self, widget: Id, receiver: Receiver<E, M>) -> Result<(), Error>;
+
+  /// Send a type-erased message to the given widget.
+  ///
+  /// The message is dispatched to the [`Receiver`] the widget
+  /// registered for the message's actual type, if any, and
+  /// [`Error::MessageNotAccepted`] is reported otherwise. If the
+  /// receiver was created for a different widget type,
+  /// [`Error::WidgetTypeMismatch`] is reported. Refer to
+  /// [`Ui::send_typed`] for a typed version of this method.
+  async fn send_any(
+    &mut self,
+    widget: Id,
+    message: Box<dyn Any>,
+  ) -> Result<Option<Box<dyn Any>>, Error>;
+
+  /// Register a responder for queries of a given type with the given
+  /// widget.
+  ///
+  /// The [`Responder`] has to have been created for the widget's type,
+  /// or [`Error::WidgetTypeMismatch`] is reported. Just as with
+  /// [`add_receiver`][Self::add_receiver], the type of a widget still
+  /// being constructed cannot be checked yet. A responder registered
+  /// previously for the same query type is replaced. See
+  /// [`Responds`][crate::Responds] for details.
+  fn add_responder(&mut self, widget: Id, responder: Responder<E, M>) -> Result<(), Error>;
+
+  /// Send a type-erased query to the given widget, returning the
+  /// type-erased reply.
+  ///
+  /// The query is dispatched to the [`Responder`] the widget
+  /// registered for the query's actual type, if any, and
+  /// [`Error::QueryNotAccepted`] is reported otherwise. If the
+  /// responder was created for a different widget type,
+  /// [`Error::WidgetTypeMismatch`] is reported. Refer to
+  /// [`Ui::request`] for a typed version of this method.
+  async fn request_any(&mut self, widget: Id, query: Box<dyn Any>) -> Result<Box<dyn Any>, Error>;
+
+  /// Subscribe the given widget to the provided topic.
+  ///
+  /// The method returns `false` if the widget was already subscribed to
+  /// the topic. Subscriptions end when the widget is removed.
+  fn subscribe(&mut self, widget: Id, topic: Topic) -> bool;
+
+  /// Unsubscribe the given widget from the provided topic, returning
+  /// whether it was subscribed.
+  fn unsubscribe(&mut self, widget: Id, topic: &Topic) -> bool;
+
+  /// Publish the provided message on the given topic.
+  ///
+  /// A copy of the message is delivered via [`Handleable::react`] to
+  /// every widget subscribed to the topic, in the order in which they
+  /// subscribed. Similar to [`broadcast`][Self::broadcast], the set of
+  /// subscribers is determined up front and subscribers removed while
+  /// the message is being delivered are skipped. Any message returned
+  /// by a subscriber is discarded. The method returns the number of
+  /// subscribers the message was delivered to.
+  async fn publish(&mut self, topic: &Topic, message: M) -> usize
+  where
+    M: Clone;
+
+  /// Set a timer for the given widget, firing once `duration` has
+  /// elapsed.
+  ///
+  /// If `repeat` is `true`, the timer keeps firing every `duration`
+  /// until it is cancelled. Timers fire as part of [`Ui::fire_timers`],
+  /// which invokes the widget's [`Handleable::timer_fired`] handler.
+  /// Timers are cancelled when the widget is removed. A timer whose
+  /// deadline is too far in the future to be represented, e.g., one
+  /// set with [`Duration::MAX`], never fires.
+  ///
+  /// Repeating timers require a non-zero `duration`;
+  /// [`Error::ZeroInterval`] is reported otherwise.
+  fn set_timer(
+    &mut self,
+    widget: Id,
+    duration: Duration,
+    repeat: bool,
+  ) -> Result<TimerId, Error>;
+
+  /// Cancel the given timer.
+  ///
+  /// The method returns `false` if the timer already expired or got
+  /// cancelled before.
+  fn cancel_timer(&mut self, timer: TimerId) -> bool;
+
+  /// Spawn a task running the provided future on behalf of the given
+  /// widget.
+  ///
+  /// Tasks are driven by [`Ui::poll_tasks`] and [`Ui::run_until`].
+  /// Once the future completed, its output is delivered to the widget
+  /// as a message, just as if it had been [`post`][Self::post]ed.
+  /// Tasks are cancelled, i.e., their future is dropped, when the
+  /// widget is removed.
+  fn spawn(&mut self, widget: Id, future: Pin<Box<dyn Future<Output = M>>>) -> TaskId;
+
+  /// Cancel the given task.
+  ///
+  /// The method returns `false` if the task already completed or got
+  /// cancelled before.
+  fn cancel_task(&mut self, task: TaskId) -> bool;
+
+  /// Queue up the provided message for delivery to the given widget.
+  ///
+  /// Unlike [`send`][Self::send], this method does not invoke the
+  /// widget's [`Handleable::react`] handler right away. Rather, the
+  /// message is delivered by [`Ui::drain_messages`], which
+  /// [`Ui::handle`] invokes once handling of the event concluded.
+  /// Messages are delivered in the order in which they were posted and
+  /// any response is discarded. Messages for widgets that have been
+  /// removed in the meantime are dropped.
+  fn post(&mut self, widget: Id, message: M);
+
+  /// Send the provided message up the widget hierarchy, starting with
+  /// the parent of the given widget.
+  ///
+  /// Each ancestor is offered the message via [`Handleable::react`].
+  /// If an ancestor returns `Some` message, that message is passed on
+  /// to its parent in turn, similar to how unhandled events bubble up.
+  /// Propagation stops once an ancestor returns `None`. The method
+  /// returns the message returned by the last ancestor, i.e., `None` if
+  /// the message got handled and `Some` if it reached the root widget
+  /// unhandled. Note that unlike events, messages are not confined to
+  /// the active modal scope.
+  async fn send_up(&mut self, from: Id, message: M) -> Option<M>;
+
+  /// Send a copy of the provided message to all widgets in the subtree
+  /// rooted at the given widget.
+  ///
+  /// The message is delivered via [`Handleable::react`] in pre-order,
+  /// i.e., each widget receives it before its children, and children
+  /// are visited in z-order, from highest to lowest. The set of
+  /// receiving widgets is determined up front: widgets added while the
+  /// message is being delivered do not receive it, while removed ones
+  /// are skipped.
+  ///
+  /// A widget consumes the message by returning `None` from its
+  /// handler; any message returned otherwise is discarded. The method
+  /// returns whether any widget consumed the message. Refer to
+  /// [`BroadcastOpts`] for ways to control delivery.
+  async fn broadcast(&mut self, root: Id, message: M, opts: BroadcastOpts) -> bool
+  where
+    M: Clone;
+
+  /// Send the provided message to the given widget, without
+  /// transferring ownership of the message, reporting an error if the
+  /// provided [`Id`] is invalid.
+  async fn try_call(&mut self, widget: Id, message: &mut M) -> Result<Option<M>, Error>;
+
   /// Force re-handling of an event starting with the provided widget.
   ///
   /// Re-handling can be useful in a certain limited set of
@@ -331,14 +994,15 @@ pub trait MutCap<E, M>: Cap + Deref<Target = dyn Cap> {
   /// focused widget.
   ///
   /// Note that event rehandling does not trigger event hooks again.
-  /// Hooks will already have been invoked for the original event. If
-  /// you need the event to pass through hooks again, you will have to
-  /// pass it to [`Ui::handle`] instead.
+  /// Hooks will already have been invoked for the original event.
+  /// Similarly, no capture phase (see [`Handleable::capture`]) takes
+  /// place for the re-handled event. If you need the event to pass
+  /// through hooks again, you will have to pass it to [`Ui::handle`]
+  /// instead.
   async fn rehandle(&mut self, widget: Id, event: E) -> Option<E>;
 }
 
 
-#[cfg(debug_assertions)]
 fn get_next_ui_id() -> usize {
   static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
 
@@ -352,8 +1016,6 @@ where
   E: 'static,
   M: 'static,
 {
-  /// No event hook is present.
-  None,
   /// An event hook handler has been installed, but not yet invoked.
   ///
   /// This variant is used to prevent delivery of post-hook events when
@@ -369,19 +1031,55 @@ where
   E: 'static,
   M: 'static,
 {
-  fn is_none(&self) -> bool {
-    matches!(self, Self::None)
+  fn hook_fn(&self) -> &EventHookFn<E, M> {
+    match self {
+      Self::Installed(D(hook_fn)) | Self::Invoked(D(hook_fn)) => hook_fn,
+    }
   }
 
-  fn into_hook_fn(self) -> Option<EventHookFn<E, M>> {
+  fn into_hook_fn(self) -> EventHookFn<E, M> {
     match self {
-      Self::Installed(D(hook_fn)) | Self::Invoked(D(hook_fn)) => Some(hook_fn),
-      Self::None => None,
+      Self::Installed(D(hook_fn)) | Self::Invoked(D(hook_fn)) => hook_fn,
     }
   }
 }
 
 
+impl<E, M> dyn MutCap<E, M> + '_
+where
+  E: 'static,
+  M: 'static,
+{
+  /// Send a message of type `T` to the given widget.
+  ///
+  /// This method is the counterpart of [`Ui::send_typed`] for usage
+  /// from within widgets.
+  pub async fn send_typed<T>(&mut self, widget: Id, message: T) -> Result<Option<T>, Error>
+  where
+    T: 'static,
+  {
+    let response = self.send_any(widget, Box::new(message)).await?;
+    // Receivers always respond with a message of the type they
+    // received.
+    Ok(response.map(|response| *response.downcast::<T>().unwrap()))
+  }
+
+  /// Send a query of type `Q` to the given widget, returning its
+  /// reply.
+  ///
+  /// This method is the counterpart of [`Ui::request`] for usage from
+  /// within widgets.
+  pub async fn request<Q>(&mut self, widget: Id, query: Q) -> Result<Q::Reply, Error>
+  where
+    Q: Query,
+  {
+    let reply = self.request_any(widget, Box::new(query)).await?;
+    // Responders always reply with the query's reply type.
+    Ok(*reply.downcast::<Q::Reply>().unwrap())
+  }
+}
+
+
 /// This type contains data that is common to all widgets.
 #[derive(Debug)]
 struct WidgetData<E, M>
@@ -399,27 +1097,161 @@ where
   ///
   /// Children are ordered by their z-index. The widget at index zero
   /// has the highest z-index (i.e., covers all below), the last one the
-  /// lowest. If one of the children is the focused one, it will be at
-  /// index zero.
+  /// lowest. Focusing a widget moves it to index zero, but explicit
+  /// z-order changes may move other widgets above it subsequently.
   // Note that unfortunately there is no straight forward way to make
   // this a Vec<Index> because we cannot use an impl trait return type
   // for the `children` method present in `Cap`.
   children: Vec<Id>,
-  /// An optional event hook that may be registered for the widget.
-  event_hook: EventHook<E, M>,
+  /// The event hooks installed by the widget, along with their
+  /// registration sequence numbers.
+  event_hooks: Vec<(usize, EventHook<E, M>)>,
+  /// The receivers for messages of types other than `M` registered for
+  /// the widget.
+  receivers: Vec<Receiver<E, M>>,
+  /// The responders for typed queries registered for the widget.
+  responders: Vec<Responder<E, M>>,
+  /// The topics the widget is subscribed to.
+  topics: Vec<Topic>,
+  /// The event filters installed by the widget, along with their
+  /// registration sequence numbers.
+  event_filters: Vec<(usize, D<EventFilterFn<E, M>>)>,
   /// Flag indicating the widget's visibility state.
   visible: bool,
+  /// Flag indicating whether the widget takes part in focus traversal.
+  focusable: bool,
+  /// The widget's optional tab index, taking precedence over document
+  /// order during focus traversal.
+  tab_index: Option<u32>,
+  /// The bounding box the widget received when the `Ui` got rendered
+  /// last, if it got rendered at all.
+  bbox: Cell<Option<BBox>>,
+  /// A number reflecting the widget's creation order.
+  ///
+  /// Because the order of children changes as widgets get focused, we
+  /// use this number for establishing a stable document order.
+  sequence: usize,
 }
 
 impl<E, M> WidgetData<E, M> {
-  fn new(parent_idx: Option<Index>, data: Box<dyn Any>) -> Self {
+  fn new(parent_idx: Option<Index>, data: Box<dyn Any>, sequence: usize) -> Self {
     Self {
       parent_idx,
       data,
       children: Default::default(),
-      event_hook: EventHook::None,
+      event_hooks: Vec::new(),
+      event_filters: Vec::new(),
+      receivers: Vec::new(),
+      responders: Vec::new(),
+      topics: Vec::new(),
       visible: true,
+      focusable: true,
+      tab_index: None,
+      bbox: Cell::new(None),
+      sequence,
+    }
+  }
+}
+
+
+/// A slot in the list of widgets managed by a [`Ui`].
+#[derive(Debug)]
+struct Slot<E, M>
+where
+  E: 'static,
+  M: 'static,
+{
+  /// The generation of the slot, incremented each time the widget
+  /// occupying it is removed.
+  generation: usize,
+  /// The widget along with its data, if the slot is occupied.
+  entry: Option<(WidgetData<E, M>, Rc<dyn Widget<E, M>>)>,
+}
+
+
+/// The list of widgets managed by a [`Ui`] along with their associated
+/// data.
+struct Widgets<E, M>
+where
+  E: 'static,
+  M: 'static,
+{
+  /// The slots housing the widgets.
+  slots: Vec<Slot<E, M>>,
+  /// The indices of all slots not currently occupied by a widget.
+  free: Vec<Index>,
+}
+
+impl<E, M> Widgets<E, M> {
+  fn new() -> Self {
+    Self {
+      slots: Vec::new(),
+      free: Vec::new(),
+    }
+  }
+
+  /// Check whether the given `Id` refers to a widget that has not been
+  /// removed.
+  fn contains(&self, id: Id) -> bool {
+    self
+      .slots
+      .get(id.idx.idx)
+      .map_or(false, |slot| slot.generation == id.generation && slot.entry.is_some())
+  }
+
+  /// Add a widget, returning the `Index` it is stored at.
+  ///
+  /// Slots of previously removed widgets are reused.
+  fn push(&mut self, data: WidgetData<E, M>, widget: Rc<dyn Widget<E, M>>) -> Index {
+    if let Some(idx) = self.free.pop() {
+      let slot = &mut self.slots[idx.idx];
+      debug_assert!(slot.entry.is_none());
+      slot.entry = Some((data, widget));
+      idx
+    } else {
+      let idx = Index::new(self.slots.len());
+      self.slots.push(Slot {
+        generation: 0,
+        entry: Some((data, widget)),
+      });
+      idx
+    }
+  }
+
+  /// Remove the widget at the given `Index`, making the slot available
+  /// for reuse.
+  fn take(&mut self, idx: Index) -> Option<(WidgetData<E, M>, Rc<dyn Widget<E, M>>)> {
+    let slot = &mut self.slots[idx.idx];
+    let entry = slot.entry.take();
+    if entry.is_some() {
+      slot.generation = slot.generation.wrapping_add(1);
+      self.free.push(idx);
     }
+    entry
+  }
+
+  fn is_empty(&self) -> bool {
+    self.slots.is_empty()
+  }
+}
+
+impl<E, M> IndexOp<Index> for Widgets<E, M> {
+  type Output = (WidgetData<E, M>, Rc<dyn Widget<E, M>>);
+
+  fn index(&self, idx: Index) -> &Self::Output {
+    self.slots[idx.idx]
+      .entry
+      .as_ref()
+      .expect("The given Id refers to a removed widget")
+  }
+}
+
+impl<E, M> IndexMut<Index> for Widgets<E, M> {
+  fn index_mut(&mut self, idx: Index) -> &mut Self::Output {
+    self.slots[idx.idx]
+      .entry
+      .as_mut()
+      .expect("The given Id refers to a removed widget")
   }
 }
 
@@ -432,18 +1264,61 @@ where
 {
   /// The UI's identifier.
   ///
-  /// This member is used only for debugging matters.
-  #[cfg(debug_assertions)]
+  /// This member is used for detecting `Id`s belonging to a different
+  /// `Ui`.
   id: usize,
   /// The list of widgets along their associated data.
-  widgets: Vec<(WidgetData<E, M>, Rc<dyn Widget<E, M>>)>,
+  widgets: Widgets<E, M>,
   /// An object dispatching events to hooks.
   hooker: &'static dyn Hooker<E, M>,
-  /// A list of widget indices that have installed an event hook
-  /// handler.
-  hooked: Rc<Vec<Index>>,
+  /// A list of all installed event hook handlers, in invocation order.
+  hooked: Rc<Vec<HookEntry>>,
+  /// The sequence number to assign to the next event hook installed.
+  next_hook_sequence: usize,
+  /// A list of all installed event filters, in invocation order.
+  filtered: Rc<Vec<FilterEntry>>,
+  /// The sequence number to assign to the next event filter installed.
+  next_filter_sequence: usize,
   /// The index of the currently focused widget, if any.
   focused: Option<Index>,
+  /// Focus change notifications yet to be delivered, in the order in
+  /// which they occurred.
+  focus_changes: VecDeque<(Id, bool)>,
+  /// The indices of all widgets that have been focused at some point,
+  /// ordered from least to most recently focused.
+  focus_history: Vec<Index>,
+  /// The stack of modal scope root widgets, with the active one last.
+  modal_roots: Vec<Index>,
+  /// Messages posted but not yet delivered, along with their
+  /// recipients.
+  messages: VecDeque<(Id, M)>,
+  /// The index of the widget currently under the pointer, if any.
+  hovered: Option<Index>,
+  /// Widgets no longer hovered that have yet to be notified about the
+  /// pointer leaving them.
+  hover_leaves: VecDeque<Id>,
+  /// The index of the widget currently grabbing the pointer, if any.
+  grabbed: Option<Index>,
+  /// The source widget and payload of the ongoing drag operation, if
+  /// any.
+  drag: Option<(Index, Box<dyn Any>)>,
+  /// The subscribers of each topic, in order of subscription.
+  subscriptions: HashMap<Topic, Vec<Index>>,
+  /// All active timers.
+  timers: Vec<Timer>,
+  /// The sequence number to assign to the next timer being set.
+  next_timer_sequence: usize,
+  /// The function used for retrieving the current time.
+  clock: Box<dyn Fn() -> Instant>,
+  /// All tasks that have not yet completed.
+  tasks: Vec<Task<M>>,
+  /// The sequence number to assign to the next task being spawned.
+  next_task_sequence: usize,
+  /// The waker of the [`Ui::run_until`] invocation currently driving
+  /// tasks, if any.
+  task_driver: Arc<Mutex<Option<Waker>>>,
+  /// The sequence number to assign to the next widget being created.
+  next_sequence: usize,
 }
 
 impl<E, M> Ui<E, M> {
@@ -457,15 +1332,33 @@ impl<E, M> Ui<E, M> {
     static NOT_HOOKED: NotHooked = NotHooked {};
 
     let mut ui = Self {
-      #[cfg(debug_assertions)]
       id: get_next_ui_id(),
-      widgets: Default::default(),
+      widgets: Widgets::new(),
       hooker: &NOT_HOOKED,
       hooked: Default::default(),
+      next_hook_sequence: 0,
+      filtered: Default::default(),
+      next_filter_sequence: 0,
       focused: None,
+      focus_changes: VecDeque::new(),
+      focus_history: Vec::new(),
+      modal_roots: Vec::new(),
+      messages: VecDeque::new(),
+      hovered: None,
+      hover_leaves: VecDeque::new(),
+      grabbed: None,
+      drag: None,
+      subscriptions: HashMap::new(),
+      timers: Vec::new(),
+      next_timer_sequence: 0,
+      clock: Box::new(Instant::now),
+      tasks: Vec::new(),
+      next_task_sequence: 0,
+      task_driver: Default::default(),
+      next_sequence: 0,
     };
 
-    let id = ui._add_widget(None, new_data, new_root_widget);
+    let id = ui._add_widget(None, 0, new_data, new_root_widget);
     debug_assert_eq!(id.idx.idx, 0);
     (ui, id)
   }
@@ -483,24 +1376,30 @@ impl<E, M> Ui<E, M> {
     W: FnOnce(Id, &mut dyn MutCap<E, M>) -> Box<dyn Widget<E, M>>,
   {
     let parent_idx = self.validate(parent);
-    self._add_widget(Some(parent_idx), new_data, new_widget)
+    self._add_widget(Some(parent_idx), usize::MAX, new_data, new_widget)
   }
 
-  /// Add a widget to the `Ui`.
-  fn _add_widget<D, W>(&mut self, parent_idx: Option<Index>, new_data: D, new_widget: W) -> Id
+  /// Add a widget to the `Ui`, inserting it at the given z-order
+  /// `position` in the parent's list of children.
+  fn _add_widget<D, W>(
+    &mut self,
+    parent_idx: Option<Index>,
+    position: usize,
+    new_data: D,
+    new_widget: W,
+  ) -> Id
   where
     D: FnOnce() -> Box<dyn Any>,
     W: FnOnce(Id, &mut dyn MutCap<E, M>) -> Box<dyn Widget<E, M>>,
   {
-    let idx = Index::new(self.widgets.len());
-    let id = Id::new(idx.idx, self);
-
     // Because we have not created the actual widget yet, just install a
     // placeholder in its stead.
     let dummy = Placeholder;
     let data = new_data();
-    let data = WidgetData::new(parent_idx, data);
-    self.widgets.push((data, Rc::new(dummy)));
+    let data = WidgetData::new(parent_idx, data, self.next_sequence);
+    self.next_sequence += 1;
+    let idx = self.widgets.push(data, Rc::new(dummy));
+    let id = Id::new(idx, self);
 
     // The widget is already linked to its parent but the parent needs to
     // know about the child as well. We do that registration before the
@@ -508,7 +1407,9 @@ impl<E, M> Ui<E, M> {
     // that a widget's ID is part of the list of IDs managed by its
     // parent.
     if let Some(parent_idx) = parent_idx {
-      self.widgets[parent_idx.idx].0.children.push(id)
+      let children = &mut self.widgets[parent_idx].0.children;
+      let position = position.min(children.len());
+      children.insert(position, id)
     }
 
     // TODO: Consider making NewWidgetFn return an Rc instead of a Box
@@ -521,25 +1422,45 @@ impl<E, M> Ui<E, M> {
     // Note that because we store the children separately as part of an
     // `WidgetData` object there is no need for us to do anything about
     // them.
-    self.widgets[idx.idx].1 = widget;
+    self.widgets[idx].1 = widget;
     id
   }
 
   /// Validate an `Id`, converting it into the internally used `Index`.
+  ///
+  /// # Panics
+  ///
+  /// This method panics if the `Id` is invalid.
   #[inline]
   fn validate(&self, id: Id) -> Index {
-    #[cfg(debug_assertions)]
-    debug_assert_eq!(id.ui_id, self.id, "The given Id belongs to a different Ui");
-    id.idx
+    match self.try_validate(id) {
+      Ok(idx) => idx,
+      Err(err) => panic!("{err}"),
+    }
+  }
+
+  /// Validate an `Id`, converting it into the internally used `Index`
+  /// or reporting an error if the `Id` is invalid.
+  #[inline]
+  fn try_validate(&self, id: Id) -> Result<Index, Error> {
+    if id.ui_id != self.id {
+      return Err(Error::ForeignUi)
+    }
+
+    if self.widgets.contains(id) {
+      Ok(id.idx)
+    } else {
+      Err(Error::InvalidId)
+    }
   }
 
   /// Lookup a widget from an `Index`.
   fn lookup(&self, idx: Index) -> &dyn Widget<E, M> {
-    self.widgets[idx.idx].1.as_ref()
+    self.widgets[idx].1.as_ref()
   }
 
   fn children(&self, idx: Index) -> ChildIter<'_> {
-    self.widgets[idx.idx].0.children.iter()
+    self.widgets[idx].0.children.iter()
   }
 
   /// Show the widget with the given `Index` and recursively all its parents.
@@ -555,7 +1476,7 @@ impl<E, M> Ui<E, M> {
     // that before the change.
     reorder_fn(self, idx);
 
-    let data = &mut self.widgets[idx.idx].0;
+    let data = &mut self.widgets[idx].0;
     data.visible = true;
 
     if let Some(parent_idx) = data.parent_idx {
@@ -569,20 +1490,20 @@ impl<E, M> Ui<E, M> {
   where
     F: FnOnce(&Ui<E, M>, &[Id]) -> usize,
   {
-    if let Some(parent_idx) = self.widgets[idx.idx].0.parent_idx {
+    if let Some(parent_idx) = self.widgets[idx].0.parent_idx {
       // First retrieve the index of the widget we are interested in
       // from its parent's list of children.
-      let children = &self.widgets[parent_idx.idx].0.children;
-      let id = Id::new(idx.idx, self);
+      let children = &self.widgets[parent_idx].0.children;
+      let id = Id::new(idx, self);
       let cur_idx = children.iter().position(|x| *x == id).unwrap();
 
       // Now remove said widget from the list of children.
-      let id = self.widgets[parent_idx.idx].0.children.remove(cur_idx);
+      let id = self.widgets[parent_idx].0.children.remove(cur_idx);
       // Next find the spot where to insert the widget as the first
       // hidden child.
-      let new_idx = new_idx_fn(self, &self.widgets[parent_idx.idx].0.children);
+      let new_idx = new_idx_fn(self, &self.widgets[parent_idx].0.children);
       // And reinsert it at this spot.
-      self.widgets[parent_idx.idx].0.children.insert(new_idx, id)
+      self.widgets[parent_idx].0.children.insert(new_idx, id)
     } else {
       // No parent. Nothing to do.
     }
@@ -599,19 +1520,19 @@ impl<E, M> Ui<E, M> {
   }
 
   fn is_visible(&self, idx: Index) -> bool {
-    self.widgets[idx.idx].0.visible
+    self.widgets[idx].0.visible
   }
 
   fn is_displayed(&self, idx: Index) -> bool {
-    let data = &self.widgets[idx.idx].0;
+    let data = &self.widgets[idx].0;
     data.visible && data.parent_idx.map_or(true, |x| self.is_displayed(x))
   }
 
   fn is_top_most_child(&self, idx: Index) -> bool {
-    let parent_idx = self.widgets[idx.idx].0.parent_idx;
+    let parent_idx = self.widgets[idx].0.parent_idx;
 
     if let Some(parent_idx) = parent_idx {
-      let children = &self.widgets[parent_idx.idx].0.children;
+      let children = &self.widgets[parent_idx].0.children;
       children[0].idx == idx
     } else {
       true
@@ -619,19 +1540,325 @@ impl<E, M> Ui<E, M> {
   }
 
   fn focus(&mut self, idx: Index) {
+    // While a modal scope is active, the focus may not leave it.
+    if !self.is_in_modal_scope(idx) {
+      return
+    }
+
     // We want to provide the invariant that a focused widget needs to
     // be visible.
     self.show(idx, Ui::reorder_as_focused);
-    self.focused = Some(idx);
+    self.set_focused(Some(idx));
   }
 
-  /// Render the `Ui` with the given `Renderer`.
-  pub fn render(&self, renderer: &dyn Renderer) {
-    // We cannot simply iterate through all widgets in `self.widgets`
-    // when rendering, because we need to take parent-child
-    // relationships into account in case widgets cover each other.
-    let idx = self.validate(self.root_id());
-    let root = self.lookup(idx);
+  /// Check whether the widget with the given `Index` is part of the
+  /// active modal scope (if any).
+  fn is_in_modal_scope(&self, idx: Index) -> bool {
+    self
+      .modal_roots
+      .last()
+      .map_or(true, |root_idx| self.is_descendant_of(idx, *root_idx))
+  }
+
+  /// Change the focused widget, queuing up the corresponding focus
+  /// change notifications.
+  fn set_focused(&mut self, focused: Option<Index>) {
+    if self.focused != focused {
+      if let Some(idx) = self.focused {
+        let id = Id::new(idx, self);
+        self.focus_changes.push_back((id, false));
+      }
+      if let Some(idx) = focused {
+        let id = Id::new(idx, self);
+        self.focus_changes.push_back((id, true));
+        self.focus_history.retain(|x| *x != idx);
+        self.focus_history.push(idx);
+      }
+      self.focused = focused;
+    }
+  }
+
+  /// Focus the most recently focused widget that is still displayed
+  /// (and part of the active modal scope), if any.
+  fn focus_previous(&mut self) {
+    let idx = self
+      .focus_history
+      .iter()
+      .rev()
+      .copied()
+      .find(|idx| self.is_displayed(*idx) && self.is_in_modal_scope(*idx));
+
+    match idx {
+      Some(idx) => self.focus(idx),
+      None => self.set_focused(None),
+    }
+  }
+
+  /// Make sure that the focused widget is displayed, returning the
+  /// focus to a previously focused widget otherwise.
+  fn restore_focus(&mut self) {
+    if let Some(focused) = self.focused {
+      if !self.is_displayed(focused) {
+        self.focus_previous()
+      }
+    }
+  }
+
+  /// Notify widgets that stopped being hovered without the pointer
+  /// moving about the pointer leaving them.
+  ///
+  /// Notifications for widgets that have been removed in the meantime
+  /// are discarded.
+  async fn notify_hover_leaves(&mut self) {
+    while let Some(id) = self.hover_leaves.pop_front() {
+      if let Ok(idx) = self.try_validate(id) {
+        let widget = Rc::clone(&self.widgets[idx].1);
+        widget.hover_changed(self, false).await
+      }
+    }
+  }
+
+  /// Deliver all pending focus change notifications.
+  ///
+  /// Because focus changes happen synchronously but
+  /// [`Handleable::focus_changed`] is asynchronous, notifications are
+  /// queued up and delivered in the order in which they occurred only
+  /// once this method is invoked. [`Ui::handle`] does so before and
+  /// after handling the provided event, so an explicit invocation is
+  /// only necessary for focus changes performed outside of event
+  /// handling.
+  ///
+  /// Notifications for widgets that have been removed in the meantime
+  /// are discarded.
+  pub async fn notify_focus_changes(&mut self) {
+    while let Some((id, gained)) = self.focus_changes.pop_front() {
+      if let Ok(idx) = self.try_validate(id) {
+        let widget = Rc::clone(&self.widgets[idx].1);
+        widget.focus_changed(self, gained).await
+      }
+    }
+  }
+
+  /// Remove the widget with the given `Index` along with all its
+  /// children.
+  fn remove(&mut self, idx: Index) {
+    let parent_idx = self.widgets[idx].0.parent_idx;
+    let parent_idx = parent_idx.expect("The root widget cannot be removed");
+
+    let children = &mut self.widgets[parent_idx].0.children;
+    let position = children.iter().position(|x| x.idx == idx).unwrap();
+    let _ = children.remove(position);
+
+    let mut lost_focus = false;
+    let mut to_remove = vec![idx];
+    while let Some(idx) = to_remove.pop() {
+      if self.focused == Some(idx) {
+        self.set_focused(None);
+        lost_focus = true;
+      }
+      self.focus_history.retain(|x| *x != idx);
+      self.modal_roots.retain(|x| *x != idx);
+      if self.hovered == Some(idx) {
+        self.hovered = None;
+        self.hover_leaves.push_back(Id::new(idx, self));
+      }
+      if self.grabbed == Some(idx) {
+        self.grabbed = None;
+      }
+      if matches!(self.drag, Some((source, _)) if source == idx) {
+        self.drag = None;
+      }
+      self.timers.retain(|timer| timer.id.widget.idx != idx);
+      self.tasks.retain(|task| task.id.widget.idx != idx);
+
+      if !self.widgets[idx].0.event_hooks.is_empty() {
+        let () = Rc::make_mut(&mut self.hooked).retain(|entry| entry.idx != idx);
+      }
+      if !self.widgets[idx].0.event_filters.is_empty() {
+        let () = Rc::make_mut(&mut self.filtered).retain(|entry| entry.idx != idx);
+      }
+
+      // Dropping the slot's content releases the widget's data as well
+      // as our reference to the widget itself.
+      let (data, _widget) = self.widgets.take(idx).unwrap();
+      for topic in &data.topics {
+        self.remove_subscriber(topic, idx)
+      }
+      to_remove.extend(data.children.iter().map(|child| child.idx));
+    }
+
+    if lost_focus {
+      self.focus_previous()
+    }
+  }
+
+  /// Remove the widget with the given `Index` from the subscribers of
+  /// the provided topic.
+  fn remove_subscriber(&mut self, topic: &Topic, idx: Index) {
+    if let Some(subscribers) = self.subscriptions.get_mut(topic) {
+      subscribers.retain(|x| *x != idx);
+      if subscribers.is_empty() {
+        let _subscribers = self.subscriptions.remove(topic);
+      }
+    }
+  }
+
+  /// Check that the widget with the given `Index` is of the expected
+  /// type.
+  ///
+  /// A widget that is still being constructed is represented by a
+  /// placeholder, the type of which only passes the check if
+  /// `allow_placeholder` is `true`.
+  fn check_widget_type(
+    &self,
+    idx: Index,
+    expected: TypeId,
+    allow_placeholder: bool,
+  ) -> Result<(), Error> {
+    let actual = Widget::type_id(self.widgets[idx].1.as_ref());
+    if actual == expected || (allow_placeholder && actual == TypeId::of::<Placeholder>()) {
+      Ok(())
+    } else {
+      Err(Error::WidgetTypeMismatch { expected, actual })
+    }
+  }
+
+  /// Check whether the widget with the given `Index` is `ancestor` or
+  /// one of its descendants.
+  fn is_descendant_of(&self, idx: Index, ancestor: Index) -> bool {
+    idx == ancestor
+      || self.widgets[idx]
+        .0
+        .parent_idx
+        .map_or(false, |parent_idx| self.is_descendant_of(parent_idx, ancestor))
+  }
+
+  /// Move the widget with the given `Index` to a new parent.
+  fn set_parent(&mut self, idx: Index, parent_idx: Index, position: usize) -> Result<(), Error> {
+    if self.is_descendant_of(parent_idx, idx) {
+      return Err(Error::Cycle)
+    }
+
+    // The root widget is an ancestor of every other widget and so we
+    // know we can't be dealing with it here.
+    let old_parent_idx = self.widgets[idx].0.parent_idx.unwrap();
+    let children = &mut self.widgets[old_parent_idx].0.children;
+    let cur_idx = children.iter().position(|x| x.idx == idx).unwrap();
+    let id = children.remove(cur_idx);
+
+    let children = &mut self.widgets[parent_idx].0.children;
+    let position = position.min(children.len());
+    children.insert(position, id);
+    self.widgets[idx].0.parent_idx = Some(parent_idx);
+
+    if let Some(focused) = self.focused {
+      if self.is_descendant_of(focused, idx) {
+        if self.is_displayed(focused) && self.is_in_modal_scope(focused) {
+          // Showing a displayed widget does not change its visibility
+          // but reorders it and its parents just like focusing would.
+          self.show(focused, Ui::reorder_as_focused);
+        } else {
+          self.focus_previous()
+        }
+      }
+    }
+    self.reset_hidden_pointer_state();
+    Ok(())
+  }
+
+  /// Reset the hover state and release the pointer grab if the
+  /// respective widget is no longer displayed.
+  fn reset_hidden_pointer_state(&mut self) {
+    if let Some(hovered) = self.hovered {
+      if !self.is_displayed(hovered) {
+        self.hovered = None;
+        self.hover_leaves.push_back(Id::new(hovered, self));
+      }
+    }
+    if let Some(grabbed) = self.grabbed {
+      if !self.is_displayed(grabbed) {
+        self.grabbed = None;
+      }
+    }
+  }
+
+  /// Collect the widgets taking part in focus traversal, in traversal
+  /// order.
+  ///
+  /// Only widgets that are part of the active modal scope, if any, are
+  /// considered.
+  ///
+  /// Traversal order is document order (a pre-order walk of the widget
+  /// tree, with siblings ordered by creation), except that widgets with
+  /// a tab index come first, in ascending tab index order.
+  fn focus_chain(&self) -> Vec<Index> {
+    fn collect<E, M>(ui: &Ui<E, M>, idx: Index, chain: &mut Vec<Index>) {
+      let data = &ui.widgets[idx].0;
+      if data.visible {
+        if data.focusable {
+          chain.push(idx)
+        }
+
+        let mut children = data.children.iter().map(|x| x.idx).collect::<Vec<_>>();
+        children.sort_by_key(|x| ui.widgets[*x].0.sequence);
+        children
+          .into_iter()
+          .for_each(|child_idx| collect(ui, child_idx, chain))
+      }
+    }
+
+    let mut chain = Vec::new();
+    let root_idx = match self.modal_roots.last() {
+      Some(root_idx) => *root_idx,
+      None => self.validate(self.root_id()),
+    };
+    let () = collect(self, root_idx, &mut chain);
+    // Note that sorting is stable, so widgets sharing a tab index stay
+    // in document order.
+    chain.sort_by_key(|x| match self.widgets[*x].0.tab_index {
+      Some(tab_index) => (false, tab_index),
+      None => (true, 0),
+    });
+    chain
+  }
+
+  /// Move the focus along the focus chain.
+  fn focus_step(&mut self, forward: bool) {
+    let chain = self.focus_chain();
+    let position = self
+      .focused
+      .and_then(|focused| chain.iter().position(|x| *x == focused));
+
+    let next = match (position, forward) {
+      (Some(position), true) => chain.get(position + 1).or_else(|| chain.first()),
+      (Some(position), false) => position
+        .checked_sub(1)
+        .and_then(|position| chain.get(position))
+        .or_else(|| chain.last()),
+      (None, true) => chain.first(),
+      (None, false) => chain.last(),
+    };
+
+    if let Some(idx) = next.copied() {
+      self.focus(idx)
+    }
+  }
+
+  /// Render the `Ui` with the given `Renderer`.
+  pub fn render(&self, renderer: &dyn Renderer) {
+    // We cannot simply iterate through all widgets in `self.widgets`
+    // when rendering, because we need to take parent-child
+    // relationships into account in case widgets cover each other.
+    let idx = self.validate(self.root_id());
+    let root = self.lookup(idx);
+
+    // Forget about all bounding boxes from the previous render; widgets
+    // not rendered this time around should not be hit-testable.
+    for slot in &self.widgets.slots {
+      if let Some((data, _widget)) = &slot.entry {
+        let () = data.bbox.set(None);
+      }
+    }
 
     let () = renderer.pre_render();
     let bbox = renderer.renderable_area();
@@ -643,6 +1870,7 @@ impl<E, M> Ui<E, M> {
   /// Recursively render the given widget and its children.
   fn render_all(&self, idx: Index, widget: &dyn Widget<E, M>, renderer: &dyn Renderer, bbox: BBox) {
     if self.is_visible(idx) {
+      let () = self.widgets[idx].0.bbox.set(Some(bbox));
       // TODO: Ideally we would want to go without the recursion stuff we
       //       have. This may not be possible (efficiently) with safe
       //       Rust, though. Not sure.
@@ -674,21 +1902,464 @@ impl<E, M> Ui<E, M> {
   where
     T: Into<E>,
   {
-    async fn handle_impl<E, M>(ui: &mut Ui<E, M>, event: E) -> Option<E> {
-      // Invoke the hooks before passing the event to the widgets on the
-      // "official" route.
-      let hook_event = ui.hooker.invoke(ui, None, None, Some(&event)).await;
-      // All events go to the focused widget first.
-      let idx = ui.focused;
-      // Any hook emitted events are not passed to the widgets themselves,
-      // but just returned.
-      let unhandled = ui.try_handle_event(idx, event).await;
+    // All events go to the focused widget first.
+    let event = event.into();
+    self.handle_impl(event, &|ui| ui.focused).await
+  }
 
-      ui.hooker.invoke(ui, hook_event, unhandled, None).await
+  /// Handle an event occurring at the given point, e.g., a mouse
+  /// click.
+  ///
+  /// Rather than to the focused widget, the event is dispatched to the
+  /// top-most displayed widget whose bounding box, as received during
+  /// the last [`render`][Self::render], contains the point. If multiple
+  /// nested widgets contain the point, the innermost one receives the
+  /// event. From there, handling progresses up the widget hierarchy
+  /// just as it does for events passed to [`handle`][Self::handle].
+  /// Event filters, hooks, and capturing apply as well.
+  ///
+  /// If a modal scope is active, only widgets inside of it are
+  /// considered. If no widget contains the point, the event is
+  /// returned unhandled. While a widget grabs the pointer, it receives
+  /// the event regardless of the point; see [`MutCap::grab_pointer`].
+  pub async fn handle_at<T>(&mut self, x: u16, y: u16, event: T) -> Option<E>
+  where
+    T: Into<E>,
+  {
+    let event = event.into();
+    self.handle_impl(event, &|ui| ui.pointer_target(x, y)).await
+  }
+
+  /// Inform the `Ui` about the pointer having moved to the given point.
+  ///
+  /// The widget under the pointer is determined the same way
+  /// [`handle_at`][Self::handle_at] determines the receiver of an
+  /// event. If it differs from the previously hovered widget, both
+  /// are notified through [`Handleable::hover_changed`]. Messages
+  /// posted and focus changes made by the notified widgets are
+  /// delivered before the method returns.
+  pub async fn pointer_moved(&mut self, x: u16, y: u16) {
+    // Widgets that stopped being hovered in the meantime learn about it
+    // before any other widget gets entered.
+    let () = self.notify_hover_leaves().await;
+
+    let idx = self.pointer_target(x, y);
+    if idx == self.hovered {
+      return
     }
 
-    let event = event.into();
-    handle_impl(self, event).await
+    let left = self.hovered.map(|idx| Id::new(idx, self));
+    let entered = idx.map(|idx| Id::new(idx, self));
+    self.hovered = idx;
+
+    // Widgets may get removed by notification handlers, which is why
+    // we work with `Id`s here.
+    if let Some(Ok(idx)) = left.map(|id| self.try_validate(id)) {
+      let widget = Rc::clone(&self.widgets[idx].1);
+      widget.hover_changed(self, false).await
+    }
+    if let Some(Ok(idx)) = entered.map(|id| self.try_validate(id)) {
+      let widget = Rc::clone(&self.widgets[idx].1);
+      widget.hover_changed(self, true).await
+    }
+    self.settle().await
+  }
+
+  /// Drop the payload of the ongoing drag operation at the given point.
+  ///
+  /// The widget at the point, determined the same way
+  /// [`handle_at`][Self::handle_at] does it but ignoring any pointer
+  /// grab, is asked whether it accepts the payload. If it does not, the
+  /// question bubbles up to its ancestors. The first widget accepting
+  /// the payload receives it and its `Id` is returned. If no widget
+  /// accepts it, the payload is dropped. Either way, the drag operation
+  /// ends. Messages posted and focus changes made by the receiving
+  /// widget are delivered before the method returns.
+  pub async fn drop_at(&mut self, x: u16, y: u16) -> Option<Id> {
+    let idx = self.drop_target_idx(x, y);
+    let (source, payload) = self.drag.take()?;
+    let idx = idx?;
+    let source = Id::new(source, self);
+    let target = Id::new(idx, self);
+
+    let widget = Rc::clone(&self.widgets[idx].1);
+    let () = widget.dropped(self, source, payload).await;
+    let () = self.settle().await;
+    Some(target)
+  }
+
+  /// Retrieve the widget that would accept the payload of the ongoing
+  /// drag operation if it were dropped at the given point.
+  ///
+  /// This method can be used for highlighting drop targets while
+  /// dragging.
+  pub fn drop_target(&self, x: u16, y: u16) -> Option<Id> {
+    self.drop_target_idx(x, y).map(|idx| Id::new(idx, self))
+  }
+
+  /// Find the widget accepting the payload of the ongoing drag
+  /// operation at the given point.
+  fn drop_target_idx(&self, x: u16, y: u16) -> Option<Index> {
+    let (source, payload) = self.drag.as_ref()?;
+    let source = Id::new(*source, self);
+    let mut idx = self.hit_test(x, y)?;
+
+    loop {
+      let widget = &self.widgets[idx].1;
+      if widget.accepts_drop(self, source, payload.as_ref()) {
+        break Some(idx)
+      }
+      // As with events, we do not bubble up past the active modal
+      // scope.
+      if self.modal_roots.last() == Some(&idx) {
+        break None
+      }
+      idx = self.widgets[idx].0.parent_idx?;
+    }
+  }
+
+  /// Handle an event, dispatching it to the widget selected by
+  /// `target`.
+  async fn handle_impl(
+    &mut self,
+    event: E,
+    target: &dyn Fn(&Self) -> Option<Index>,
+  ) -> Option<E> {
+    // Let widgets know about focus and hover changes that happened
+    // since the last event got handled before handling the new one.
+    let () = self.notify_focus_changes().await;
+    let () = self.notify_hover_leaves().await;
+    // Filters get the first shot at the event and may discard it
+    // altogether.
+    let event = match self.filter_event(event).await {
+      Some(event) => {
+        // Invoke the hooks before passing the event to the widgets on
+        // the "official" route.
+        let hook_event = self.hooker.invoke(self, None, None, Some(&event)).await;
+        // Hooks may have changed the UI, so only determine the target
+        // now.
+        let idx = target(self);
+        // Any hook emitted events are not passed to the widgets
+        // themselves, but just returned.
+        let unhandled = self.dispatch_event(idx, event).await;
+
+        self.hooker.invoke(self, hook_event, unhandled, None).await
+      },
+      None => None,
+    };
+    // Messages posted while handling the event are delivered only once
+    // all handlers ran to completion.
+    let () = self.settle().await;
+    event
+  }
+
+  /// Deliver posted messages as well as focus change and hover leave
+  /// notifications until there are none left.
+  ///
+  /// Message handlers may change the focus or hide widgets and
+  /// notification handlers may post messages, so we keep going until
+  /// all queues are empty.
+  async fn settle(&mut self) {
+    while !self.messages.is_empty()
+      || !self.focus_changes.is_empty()
+      || !self.hover_leaves.is_empty()
+    {
+      let () = self.drain_messages().await;
+      let () = self.notify_focus_changes().await;
+      let () = self.notify_hover_leaves().await;
+    }
+  }
+
+  /// Send a message of type `T` to the given widget.
+  ///
+  /// The widget has to have registered a [`Receiver`] for messages of
+  /// type `T`, or [`Error::MessageNotAccepted`] is reported. See
+  /// [`Receives`][crate::Receives] for details.
+  pub async fn send_typed<T>(&mut self, widget: Id, message: T) -> Result<Option<T>, Error>
+  where
+    T: 'static,
+  {
+    let response = MutCap::send_any(self, widget, Box::new(message)).await?;
+    // Receivers always respond with a message of the type they
+    // received.
+    Ok(response.map(|response| *response.downcast::<T>().unwrap()))
+  }
+
+  /// Send a query of type `Q` to the given widget, returning its
+  /// reply.
+  ///
+  /// The widget has to have registered a [`Responder`] for queries of
+  /// type `Q`, or [`Error::QueryNotAccepted`] is reported. See
+  /// [`Responds`][crate::Responds] for details.
+  pub async fn request<Q>(&mut self, widget: Id, query: Q) -> Result<Q::Reply, Error>
+  where
+    Q: Query,
+  {
+    let reply = MutCap::request_any(self, widget, Box::new(query)).await?;
+    // Responders always reply with the query's reply type.
+    Ok(*reply.downcast::<Q::Reply>().unwrap())
+  }
+
+  /// Set the function used for retrieving the current time.
+  ///
+  /// The clock determines the deadlines of timers set by
+  /// [`MutCap::set_timer`]. By default, [`Instant::now`] is used.
+  pub fn set_clock<C>(&mut self, clock: C)
+  where
+    C: Fn() -> Instant + 'static,
+  {
+    self.clock = Box::new(clock);
+  }
+
+  /// Retrieve the point in time at which the next timer fires, if any.
+  ///
+  /// The host event loop is expected to invoke
+  /// [`fire_timers`][Self::fire_timers] once this point in time is
+  /// reached.
+  pub fn next_deadline(&self) -> Option<Instant> {
+    self.timers.iter().filter_map(|timer| timer.deadline).min()
+  }
+
+  /// Fire all timers whose deadline is not after `now`.
+  ///
+  /// Expired timers fire in order of their deadlines, each by means of
+  /// the owning widget's [`Handleable::timer_fired`] handler. A
+  /// repeating timer fires at most once per invocation; expirations it
+  /// missed are coalesced. Timers cancelled by a handler do not fire
+  /// anymore, while timers set by one are only considered by
+  /// subsequent invocations. Messages posted and focus changes made by
+  /// handlers are delivered before the method returns.
+  pub async fn fire_timers(&mut self, now: Instant) {
+    let mut expired = self
+      .timers
+      .iter()
+      .filter_map(|timer| match timer.deadline {
+        Some(deadline) if deadline <= now => Some((deadline, timer.id.sequence, timer.id)),
+        _ => None,
+      })
+      .collect::<Vec<_>>();
+    let () = expired.sort_by_key(|(deadline, sequence, _)| (*deadline, *sequence));
+
+    for (_, _, timer) in expired {
+      let position = match self.timers.iter().position(|x| x.id == timer) {
+        Some(position) => position,
+        None => continue,
+      };
+
+      match self.timers[position].interval {
+        Some(interval) => {
+          let entry = &mut self.timers[position];
+          entry.deadline = match entry
+            .deadline
+            .and_then(|deadline| deadline.checked_add(interval))
+          {
+            Some(deadline) if deadline <= now => now.checked_add(interval),
+            deadline => deadline,
+          };
+        },
+        None => {
+          let _timer = self.timers.remove(position);
+        },
+      }
+
+      // Timers are removed along with their widget, so the widget is
+      // known to exist.
+      let widget = Rc::clone(&self.widgets[timer.widget.idx].1);
+      let () = widget.timer_fired(self, timer).await;
+    }
+
+    self.settle().await
+  }
+
+  /// Poll all tasks that have been woken since they were last polled.
+  ///
+  /// The output of tasks that completed is delivered to the widget
+  /// that spawned them, along with all other messages posted in the
+  /// meantime, before the method returns. The same is true for
+  /// notifications about focus changes made while doing so. Tasks
+  /// spawned or woken in the process are only polled by subsequent
+  /// invocations.
+  pub async fn poll_tasks(&mut self) {
+    let mut i = 0;
+    while i < self.tasks.len() {
+      let task = &mut self.tasks[i];
+      if !task.waker.woken.swap(false, Ordering::AcqRel) {
+        i += 1;
+        continue
+      }
+
+      let waker = Waker::from(Arc::clone(&task.waker));
+      let mut cx = Context::from_waker(&waker);
+      match task.future.as_mut().poll(&mut cx) {
+        Poll::Ready(message) => {
+          let task = self.tasks.remove(i);
+          let () = self.messages.push_back((task.id.widget, message));
+        },
+        Poll::Pending => i += 1,
+      }
+    }
+
+    self.settle().await
+  }
+
+  /// Drive the provided future to completion, polling tasks spawned by
+  /// widgets whenever they are woken.
+  ///
+  /// This method is meant to be used by the host event loop for
+  /// awaiting the next event, e.g., by passing it a future reading
+  /// input, while keeping widget tasks progressing.
+  pub async fn run_until<F>(&mut self, future: F) -> F::Output
+  where
+    F: Future,
+  {
+    let mut future = Box::pin(future);
+
+    let output = loop {
+      let () = self.poll_tasks().await;
+
+      let output = poll_fn(|cx| {
+        // Register ourselves before checking tasks, so that we don't
+        // miss a wake up happening in between.
+        *self.task_driver.lock().unwrap() = Some(cx.waker().clone());
+
+        if let Poll::Ready(output) = future.as_mut().poll(cx) {
+          return Poll::Ready(Some(output))
+        }
+
+        let woken = self
+          .tasks
+          .iter()
+          .any(|task| task.waker.woken.load(Ordering::Acquire));
+        if woken {
+          Poll::Ready(None)
+        } else {
+          Poll::Pending
+        }
+      })
+      .await;
+
+      if let Some(output) = output {
+        break output
+      }
+    };
+
+    *self.task_driver.lock().unwrap() = None;
+    output
+  }
+
+  /// Deliver all messages queued up by [`MutCap::post`].
+  ///
+  /// Messages posted while delivering are delivered as part of the
+  /// same invocation, meaning that this method only returns once the
+  /// queue is empty. [`Ui::handle`] invokes this method after handling
+  /// an event, so an explicit invocation is only necessary for
+  /// messages posted outside of event handling.
+  pub async fn drain_messages(&mut self) {
+    while let Some((id, message)) = self.messages.pop_front() {
+      if let Ok(idx) = self.try_validate(id) {
+        let widget = Rc::clone(&self.widgets[idx].1);
+        let _response = widget.react(message, self).await;
+      }
+    }
+  }
+
+  /// Determine the widget receiving pointer events at the given point:
+  /// the one grabbing the pointer, if any, or the one at said point.
+  fn pointer_target(&self, x: u16, y: u16) -> Option<Index> {
+    match self.grabbed {
+      Some(idx) if self.is_in_modal_scope(idx) => Some(idx),
+      _ => self.hit_test(x, y),
+    }
+  }
+
+  /// Find the top-most displayed widget containing the given point,
+  /// taking into account only the active modal scope, if any.
+  fn hit_test(&self, x: u16, y: u16) -> Option<Index> {
+    let idx = match self.modal_roots.last() {
+      Some(idx) => *idx,
+      None => self.validate(self.root_id()),
+    };
+
+    if self.is_displayed(idx) {
+      self.hit_test_from(idx, x, y)
+    } else {
+      None
+    }
+  }
+
+  /// Find the innermost widget containing the given point in the
+  /// subtree rooted at the widget with the given `Index`.
+  fn hit_test_from(&self, idx: Index, x: u16, y: u16) -> Option<Index> {
+    let data = &self.widgets[idx].0;
+    match data.bbox.get() {
+      Some(bbox) if data.visible && bbox.contains(x, y) => (),
+      _ => return None,
+    }
+
+    // Children are ordered from top-most to bottom-most.
+    data
+      .children
+      .iter()
+      .find_map(|child| self.hit_test_from(child.idx, x, y))
+      .or(Some(idx))
+  }
+
+  /// Pass an event through all installed event filters.
+  async fn filter_event(&mut self, mut event: E) -> Option<E> {
+    for entry in Rc::clone(&self.filtered).as_ref() {
+      // A previously invoked filter may have removed the widget or
+      // uninstalled the filter.
+      if self.filtered.binary_search(entry).is_err() {
+        continue
+      }
+      let (data, widget) = &self.widgets[entry.idx];
+      let filter_fn = data
+        .event_filters
+        .iter()
+        .find(|(sequence, _)| *sequence == entry.sequence)
+        .map(|(_, D(filter_fn))| Rc::clone(filter_fn))
+        .unwrap();
+      let widget = Rc::clone(widget);
+      event = filter_fn(widget.as_ref(), self, event).await?;
+    }
+    Some(event)
+  }
+
+  /// Dispatch an event to the widget with the given `Index`, letting
+  /// its ancestors capture it first.
+  async fn dispatch_event(&mut self, idx: Option<Index>, mut event: E) -> Option<E> {
+    let idx = match idx {
+      Some(idx) => idx,
+      None => return Some(event),
+    };
+    let target = Id::new(idx, self);
+
+    // Collect the ancestors taking part in the capture phase. Because
+    // widgets may get removed while capturing, we have to work with
+    // `Id`s here.
+    let mut ancestors = Vec::new();
+    let mut cur_idx = idx;
+    while self.modal_roots.last() != Some(&cur_idx) {
+      match self.widgets[cur_idx].0.parent_idx {
+        Some(parent_idx) => {
+          ancestors.push(Id::new(parent_idx, self));
+          cur_idx = parent_idx;
+        },
+        None => break,
+      }
+    }
+
+    for id in ancestors.into_iter().rev() {
+      if let Ok(idx) = self.try_validate(id) {
+        let widget = Rc::clone(&self.widgets[idx].1);
+        event = widget.capture(self, event).await?;
+      }
+    }
+
+    match self.try_validate(target) {
+      Ok(idx) => self.handle_event(idx, event).await,
+      Err(..) => Some(event),
+    }
   }
 
   /// Bubble up an event until it is handled by some `Widget`.
@@ -702,9 +2373,20 @@ impl<E, M> Ui<E, M> {
       // the Ui, which in turn makes it possible to pass a mutable Ui
       // reference (in the form of a MutCap) to an immutable widget. It is
       // nothing more but a reference count bump, though.
-      let widget = Rc::clone(&self.widgets[idx.idx].1);
+      let id = Id::new(idx, self);
+      let widget = Rc::clone(&self.widgets[idx].1);
       let event = widget.handle(self, event).await;
-      let parent_idx = self.widgets[idx.idx].0.parent_idx;
+      // The widget may have removed itself while handling the event, in
+      // which case there is no one left to bubble up to.
+      let parent_idx = if !self.widgets.contains(id) {
+        None
+      } else if self.modal_roots.last() == Some(&idx) {
+        // Events do not bubble up past the root of the active modal
+        // scope.
+        None
+      } else {
+        self.widgets[idx].0.parent_idx
+      };
 
       if let Some(event) = event {
         self.try_handle_event(parent_idx, event).await
@@ -736,7 +2418,14 @@ impl<E, M> Cap for Ui<E, M> {
   /// Retrieve a reference to a widget's data.
   fn data(&self, widget: Id) -> &dyn Any {
     let idx = self.validate(widget);
-    self.widgets[idx.idx].0.data.as_ref()
+    self.widgets[idx].0.data.as_ref()
+  }
+
+  /// Retrieve a reference to a widget's data, reporting an error if
+  /// the provided `Id` is invalid.
+  fn try_data(&self, widget: Id) -> Result<&dyn Any, Error> {
+    let idx = self.try_validate(widget)?;
+    Ok(self.widgets[idx].0.data.as_ref())
   }
 
   /// Retrieve an iterator over the children. Iteration happens in
@@ -745,24 +2434,40 @@ impl<E, M> Cap for Ui<E, M> {
     self.children(self.validate(widget))
   }
 
+  /// Retrieve an iterator over the children, reporting an error if the
+  /// provided `Id` is invalid.
+  fn try_children(&self, widget: Id) -> Result<ChildIter<'_>, Error> {
+    let idx = self.try_validate(widget)?;
+    Ok(self.children(idx))
+  }
+
   /// Retrieve the `Id` of the root widget.
   fn root_id(&self) -> Id {
     debug_assert!(!self.widgets.is_empty());
     // We do not unconditionally unwrap the Option returned by as_ref()
     // here as it is possible that it is empty and we do not want to
     // panic here. This is mostly important for unit testing.
-    debug_assert_eq!(self.validate(self.widgets[0].1.id()).idx, 0);
+    debug_assert_eq!(self.validate(self.widgets[Index::new(0)].1.id()).idx, 0);
 
-    Id::new(0, self)
+    Id::new(Index::new(0), self)
   }
 
   /// Retrieve the parent of the given widget.
   fn parent_id(&self, widget: Id) -> Option<Id> {
-    let idx = self.validate(widget);
-    let parent_idx = self.widgets[idx.idx].0.parent_idx;
-    let parent_id = parent_idx.map(|x| Id::new(x.idx, self));
+    match self.try_parent_id(widget) {
+      Ok(parent_id) => parent_id,
+      Err(err) => panic!("{err}"),
+    }
+  }
+
+  /// Retrieve the parent of the given widget, reporting an error if the
+  /// provided `Id` is invalid.
+  fn try_parent_id(&self, widget: Id) -> Result<Option<Id>, Error> {
+    let idx = self.try_validate(widget)?;
+    let parent_idx = self.widgets[idx].0.parent_idx;
+    let parent_id = parent_idx.map(|x| Id::new(x, self));
     debug_assert!(parent_id.map_or(true, |x| Cap::children(self, x).any(|x| *x == widget)));
-    parent_id
+    Ok(parent_id)
   }
 
   /// Check whether a widget has its visibility flag set.
@@ -777,7 +2482,22 @@ impl<E, M> Cap for Ui<E, M> {
 
   /// Retrieve the currently focused widget.
   fn focused(&self) -> Option<Id> {
-    self.focused.map(|x| Id::new(x.idx, self))
+    self.focused.map(|x| Id::new(x, self))
+  }
+
+  /// Retrieve the widget currently under the pointer, if any.
+  fn hovered(&self) -> Option<Id> {
+    self.hovered.map(|x| Id::new(x, self))
+  }
+
+  /// Retrieve the widget currently grabbing the pointer, if any.
+  fn pointer_grab(&self) -> Option<Id> {
+    self.grabbed.map(|x| Id::new(x, self))
+  }
+
+  /// Retrieve the widget that started the ongoing drag operation.
+  fn drag_source(&self) -> Option<Id> {
+    self.drag.as_ref().map(|(x, _)| Id::new(*x, self))
   }
 
   /// Check whether the given widget is focused.
@@ -786,9 +2506,25 @@ impl<E, M> Cap for Ui<E, M> {
     let idx = self.validate(widget);
     let result = self.focused == Some(idx);
     debug_assert!(result && self.is_displayed(idx) || !result);
-    debug_assert!(result && self.is_top_most_child(idx) || !result);
     result
   }
+
+  /// Retrieve the root of the active modal scope, if any.
+  fn modal_root(&self) -> Option<Id> {
+    self.modal_roots.last().map(|x| Id::new(*x, self))
+  }
+
+  /// Check whether a widget takes part in focus traversal.
+  fn is_focusable(&self, widget: Id) -> bool {
+    let idx = self.validate(widget);
+    self.widgets[idx].0.focusable
+  }
+
+  /// Retrieve a widget's tab index, if any.
+  fn tab_index(&self, widget: Id) -> Option<u32> {
+    let idx = self.validate(widget);
+    self.widgets[idx].0.tab_index
+  }
 }
 
 #[async_trait(?Send)]
@@ -796,7 +2532,14 @@ impl<E, M> MutCap<E, M> for Ui<E, M> {
   /// Retrieve a mutable reference to a widget's data.
   fn data_mut(&mut self, widget: Id) -> &mut dyn Any {
     let idx = self.validate(widget);
-    self.widgets[idx.idx].0.data.as_mut()
+    self.widgets[idx].0.data.as_mut()
+  }
+
+  /// Retrieve a mutable reference to a widget's data, reporting an
+  /// error if the provided `Id` is invalid.
+  fn try_data_mut(&mut self, widget: Id) -> Result<&mut dyn Any, Error> {
+    let idx = self.try_validate(widget)?;
+    Ok(self.widgets[idx].0.data.as_mut())
   }
 
   /// Add a widget to the `Ui`.
@@ -809,6 +2552,60 @@ impl<E, M> MutCap<E, M> for Ui<E, M> {
     self.add_ui_widget(parent, new_data, new_widget)
   }
 
+  /// Remove a widget along with all its children from the `Ui`.
+  fn remove_widget(&mut self, widget: Id) {
+    let idx = self.validate(widget);
+    self.remove(idx)
+  }
+
+  /// Add a widget to the `Ui` at the given z-order position.
+  fn add_widget_at(
+    &mut self,
+    parent: Id,
+    position: usize,
+    new_data: Box<NewDataFn>,
+    new_widget: Box<NewWidgetFn<E, M>>,
+  ) -> Id {
+    let parent_idx = self.validate(parent);
+    self._add_widget(Some(parent_idx), position, new_data, new_widget)
+  }
+
+  /// Raise a widget to the top of its siblings' z-order.
+  fn raise(&mut self, widget: Id) {
+    let idx = self.validate(widget);
+    self.reorder(idx, |_, _| 0)
+  }
+
+  /// Lower a widget to the bottom of its siblings' z-order.
+  fn lower(&mut self, widget: Id) {
+    let idx = self.validate(widget);
+    self.reorder(idx, |_, children| children.len())
+  }
+
+  /// Reorder a widget to reside directly above the given sibling.
+  fn raise_above(&mut self, widget: Id, sibling: Id) -> Result<(), Error> {
+    let idx = self.try_validate(widget)?;
+    let sibling_idx = self.try_validate(sibling)?;
+
+    if self.widgets[idx].0.parent_idx != self.widgets[sibling_idx].0.parent_idx {
+      return Err(Error::NotSiblings)
+    }
+
+    if idx != sibling_idx {
+      self.reorder(idx, |_, children| {
+        children.iter().position(|x| *x == sibling).unwrap()
+      })
+    }
+    Ok(())
+  }
+
+  /// Move a widget along with all its children to a new parent.
+  fn set_parent(&mut self, widget: Id, new_parent: Id, position: usize) -> Result<(), Error> {
+    let idx = self.try_validate(widget)?;
+    let parent_idx = self.try_validate(new_parent)?;
+    self.set_parent(idx, parent_idx, position)
+  }
+
   /// Show a widget, i.e., set its and its parents' visibility flag.
   fn show(&mut self, widget: Id) {
     let idx = self.validate(widget);
@@ -817,12 +2614,11 @@ impl<E, M> MutCap<E, M> for Ui<E, M> {
 
   /// Hide a widget, i.e., unset its visibility flag.
   fn hide(&mut self, widget: Id) {
-    if self.is_focused(widget) {
-      self.focused = None
-    }
-
     let idx = self.validate(widget);
-    self.widgets[idx.idx].0.visible = false;
+    self.widgets[idx].0.visible = false;
+
+    self.reset_hidden_pointer_state();
+    self.restore_focus()
   }
 
   /// Focus a widget.
@@ -831,12 +2627,94 @@ impl<E, M> MutCap<E, M> for Ui<E, M> {
     self.focus(idx)
   }
 
-  /// Install or remove an event hook handler.
-  fn hook_events(
+  /// Route all pointer events to the given widget.
+  fn grab_pointer(&mut self, widget: Id) {
+    let idx = self.validate(widget);
+    if self.is_displayed(idx) {
+      self.grabbed = Some(idx);
+    }
+  }
+
+  /// Release the pointer grab, if any.
+  fn release_pointer(&mut self) {
+    self.grabbed = None;
+  }
+
+  /// Start a drag operation on behalf of the given widget.
+  fn start_drag(&mut self, source: Id, payload: Box<dyn Any>) {
+    let idx = self.validate(source);
+    self.drag = Some((idx, payload));
+  }
+
+  /// Cancel the ongoing drag operation, if any.
+  fn cancel_drag(&mut self) -> Option<Box<dyn Any>> {
+    self.drag.take().map(|(_, payload)| payload)
+  }
+
+  /// Make the given widget the root of a new modal scope.
+  fn push_modal(&mut self, widget: Id) {
+    let idx = self.validate(widget);
+    self.modal_roots.push(idx);
+
+    let in_scope = self
+      .focused
+      .map_or(false, |focused| self.is_descendant_of(focused, idx));
+    if !in_scope {
+      self.focus(idx)
+    }
+  }
+
+  /// Deactivate the most recently pushed modal scope.
+  fn pop_modal(&mut self) -> Option<Id> {
+    let idx = self.modal_roots.pop()?;
+    let id = Id::new(idx, self);
+
+    let in_scope = self
+      .focused
+      .map_or(false, |focused| self.is_displayed(focused) && self.is_in_modal_scope(focused));
+    if !in_scope {
+      self.focus_previous()
+    }
+    Some(id)
+  }
+
+  /// Set whether a widget takes part in focus traversal.
+  fn set_focusable(&mut self, widget: Id, focusable: bool) {
+    let idx = self.validate(widget);
+    self.widgets[idx].0.focusable = focusable;
+  }
+
+  /// Set or clear a widget's tab index.
+  fn set_tab_index(&mut self, widget: Id, tab_index: Option<u32>) {
+    let idx = self.validate(widget);
+    self.widgets[idx].0.tab_index = tab_index;
+  }
+
+  /// Focus the next widget in the focus chain.
+  fn focus_next(&mut self) {
+    self.focus_step(true)
+  }
+
+  /// Focus the previous widget in the focus chain.
+  fn focus_prev(&mut self) {
+    self.focus_step(false)
+  }
+
+  /// Install an event hook handler.
+  fn hook_events(&mut self, widget: Id, hook_fn: EventHookFn<E, M>) -> HookId
+  where
+    E: Mergeable,
+  {
+    self.hook_events_with_priority(widget, hook_fn, 0)
+  }
+
+  /// Install an event hook handler with the given priority.
+  fn hook_events_with_priority(
     &mut self,
     widget: Id,
-    hook_fn: Option<EventHookFn<E, M>>,
-  ) -> Option<EventHookFn<E, M>>
+    hook_fn: EventHookFn<E, M>,
+    priority: i32,
+  ) -> HookId
   where
     E: Mergeable,
   {
@@ -844,49 +2722,382 @@ impl<E, M> MutCap<E, M> for Ui<E, M> {
     self.hooker = &HOOKED;
 
     let idx = self.validate(widget);
-    let data = &mut self.widgets[idx.idx].0;
-    let result = self.hooked.binary_search(&idx);
+    let sequence = self.next_hook_sequence;
+    self.next_hook_sequence += 1;
 
-    debug_assert_eq!(result.is_ok(), !data.event_hook.is_none());
+    let entry = HookEntry {
+      priority: Reverse(priority),
+      sequence,
+      idx,
+    };
+    let hooked = Rc::make_mut(&mut self.hooked);
+    let i = hooked.partition_point(|other| *other < entry);
+    let () = hooked.insert(i, entry);
 
-    match hook_fn {
-      Some(_) => {
-        if let Err(i) = result {
-          Rc::make_mut(&mut self.hooked).insert(i, idx);
-        }
-      },
-      None => {
-        if let Ok(i) = result {
-          let _ = Rc::make_mut(&mut self.hooked).remove(i);
-        }
-      },
+    let hook = (sequence, EventHook::Installed(D(hook_fn)));
+    let () = self.widgets[idx].0.event_hooks.push(hook);
+
+    HookId { widget, sequence }
+  }
+
+  /// Uninstall a previously installed event hook handler.
+  fn unhook_events(&mut self, hook: HookId) -> Option<EventHookFn<E, M>> {
+    let idx = match self.try_validate(hook.widget) {
+      Ok(idx) => idx,
+      // All hooks of a removed widget are uninstalled already.
+      Err(Error::InvalidId) => return None,
+      Err(err) => panic!("{err}"),
     };
 
-    let event_hook = if let Some(hook_fn) = hook_fn {
-      EventHook::Installed(D(hook_fn))
-    } else {
-      EventHook::None
+    let hooks = &mut self.widgets[idx].0.event_hooks;
+    let i = hooks
+      .iter()
+      .position(|(sequence, _)| *sequence == hook.sequence)?;
+    let (_, event_hook) = hooks.remove(i);
+
+    let () = Rc::make_mut(&mut self.hooked)
+      .retain(|entry| entry.sequence != hook.sequence);
+    Some(event_hook.into_hook_fn())
+  }
+
+  /// Install an event filter.
+  fn filter_events(&mut self, widget: Id, filter_fn: EventFilterFn<E, M>) -> FilterId {
+    let idx = self.validate(widget);
+    let sequence = self.next_filter_sequence;
+    self.next_filter_sequence += 1;
+
+    // Sequence numbers only ever increase, so appending keeps the list
+    // sorted.
+    let entry = FilterEntry { sequence, idx };
+    let () = Rc::make_mut(&mut self.filtered).push(entry);
+
+    let filter = (sequence, D(filter_fn));
+    let () = self.widgets[idx].0.event_filters.push(filter);
+
+    FilterId { widget, sequence }
+  }
+
+  /// Uninstall a previously installed event filter.
+  fn unfilter_events(&mut self, filter: FilterId) -> Option<EventFilterFn<E, M>> {
+    let idx = match self.try_validate(filter.widget) {
+      Ok(idx) => idx,
+      // All filters of a removed widget are uninstalled already.
+      Err(Error::InvalidId) => return None,
+      Err(err) => panic!("{err}"),
     };
-    replace(&mut data.event_hook, event_hook).into_hook_fn()
+
+    let filters = &mut self.widgets[idx].0.event_filters;
+    let i = filters
+      .iter()
+      .position(|(sequence, _)| *sequence == filter.sequence)?;
+    let (_, D(filter_fn)) = filters.remove(i);
+
+    let () = Rc::make_mut(&mut self.filtered)
+      .retain(|entry| entry.sequence != filter.sequence);
+    Some(filter_fn)
   }
 
   /// Send the provided message to the given widget.
   async fn send(&mut self, widget: Id, message: M) -> Option<M> {
     let idx = self.validate(widget);
-    let widget = Rc::clone(&self.widgets[idx.idx].1);
+    let widget = Rc::clone(&self.widgets[idx].1);
 
     widget.react(message, self).await
   }
 
+  /// Send the provided message to the given widget, reporting an error
+  /// if the provided `Id` is invalid.
+  async fn try_send(&mut self, widget: Id, message: M) -> Result<Option<M>, Error> {
+    let idx = self.try_validate(widget)?;
+    let widget = Rc::clone(&self.widgets[idx].1);
+
+    Ok(widget.react(message, self).await)
+  }
+
   /// Send the provided message to the given widget, without
   /// transferring ownership of the message.
   async fn call(&mut self, widget: Id, message: &mut M) -> Option<M> {
     let idx = self.validate(widget);
-    let widget = Rc::clone(&self.widgets[idx.idx].1);
+    let widget = Rc::clone(&self.widgets[idx].1);
 
     widget.respond(message, self).await
   }
 
+  /// Send the provided message to the given widget, without
+  /// transferring ownership of the message, reporting an error if the
+  /// provided `Id` is invalid.
+  async fn try_call(&mut self, widget: Id, message: &mut M) -> Result<Option<M>, Error> {
+    let idx = self.try_validate(widget)?;
+    let widget = Rc::clone(&self.widgets[idx].1);
+
+    Ok(widget.respond(message, self).await)
+  }
+
+  /// Register a receiver for messages of a type other than `M`.
+  fn add_receiver(&mut self, widget: Id, receiver: Receiver<E, M>) -> Result<(), Error> {
+    let idx = self.try_validate(widget)?;
+    let () = self.check_widget_type(idx, receiver.widget_type(), true)?;
+    let receivers = &mut self.widgets[idx].0.receivers;
+    let message_type = receiver.message_type();
+
+    match receivers.iter_mut().find(|x| x.message_type() == message_type) {
+      Some(existing) => *existing = receiver,
+      None => receivers.push(receiver),
+    }
+    Ok(())
+  }
+
+  /// Send a type-erased message to the given widget.
+  async fn send_any(
+    &mut self,
+    widget: Id,
+    message: Box<dyn Any>,
+  ) -> Result<Option<Box<dyn Any>>, Error> {
+    let idx = self.try_validate(widget)?;
+    let message_type = Any::type_id(&*message);
+    let (widget, receive_fn) = {
+      let (data, widget) = &self.widgets[idx];
+      let receiver = data
+        .receivers
+        .iter()
+        .find(|x| x.message_type() == message_type)
+        .ok_or(Error::MessageNotAccepted { message_type })?;
+
+      let () = self.check_widget_type(idx, receiver.widget_type(), false)?;
+      (Rc::clone(widget), receiver.receive_fn())
+    };
+
+    Ok(receive_fn(widget.as_ref(), self, message).await)
+  }
+
+  /// Register a responder for queries of a given type.
+  fn add_responder(&mut self, widget: Id, responder: Responder<E, M>) -> Result<(), Error> {
+    let idx = self.try_validate(widget)?;
+    let () = self.check_widget_type(idx, responder.widget_type(), true)?;
+    let responders = &mut self.widgets[idx].0.responders;
+    let query_type = responder.query_type();
+
+    match responders.iter_mut().find(|x| x.query_type() == query_type) {
+      Some(existing) => *existing = responder,
+      None => responders.push(responder),
+    }
+    Ok(())
+  }
+
+  /// Send a type-erased query to the given widget.
+  async fn request_any(&mut self, widget: Id, query: Box<dyn Any>) -> Result<Box<dyn Any>, Error> {
+    let idx = self.try_validate(widget)?;
+    let query_type = Any::type_id(&*query);
+    let (widget, respond_fn) = {
+      let (data, widget) = &self.widgets[idx];
+      let responder = data
+        .responders
+        .iter()
+        .find(|x| x.query_type() == query_type)
+        .ok_or(Error::QueryNotAccepted { query_type })?;
+
+      let () = self.check_widget_type(idx, responder.widget_type(), false)?;
+      (Rc::clone(widget), responder.respond_fn())
+    };
+
+    Ok(respond_fn(widget.as_ref(), self, query).await)
+  }
+
+  /// Subscribe the given widget to the provided topic.
+  fn subscribe(&mut self, widget: Id, topic: Topic) -> bool {
+    let idx = self.validate(widget);
+    let topics = &mut self.widgets[idx].0.topics;
+    if topics.contains(&topic) {
+      return false
+    }
+
+    topics.push(topic.clone());
+    self.subscriptions.entry(topic).or_default().push(idx);
+    true
+  }
+
+  /// Unsubscribe the given widget from the provided topic.
+  fn unsubscribe(&mut self, widget: Id, topic: &Topic) -> bool {
+    let idx = self.validate(widget);
+    let topics = &mut self.widgets[idx].0.topics;
+    match topics.iter().position(|x| x == topic) {
+      Some(position) => {
+        let _topic = topics.remove(position);
+        self.remove_subscriber(topic, idx);
+        true
+      },
+      None => false,
+    }
+  }
+
+  /// Publish the provided message on the given topic.
+  async fn publish(&mut self, topic: &Topic, message: M) -> usize
+  where
+    M: Clone,
+  {
+    // Subscribers may come and go while the message is being delivered,
+    // so we capture them as `Id`s before delivering anything.
+    let subscribers = self
+      .subscriptions
+      .get(topic)
+      .map(|subscribers| {
+        subscribers
+          .iter()
+          .map(|idx| Id::new(*idx, self))
+          .collect::<Vec<_>>()
+      })
+      .unwrap_or_default();
+
+    let mut delivered = 0;
+    for id in subscribers {
+      let idx = match self.try_validate(id) {
+        Ok(idx) => idx,
+        Err(..) => continue,
+      };
+
+      let widget = Rc::clone(&self.widgets[idx].1);
+      let _response = widget.react(message.clone(), self).await;
+      delivered += 1;
+    }
+    delivered
+  }
+
+  /// Set a timer for the given widget.
+  fn set_timer(
+    &mut self,
+    widget: Id,
+    duration: Duration,
+    repeat: bool,
+  ) -> Result<TimerId, Error> {
+    let _idx = self.try_validate(widget)?;
+    if repeat && duration.is_zero() {
+      return Err(Error::ZeroInterval)
+    }
+
+    let id = TimerId {
+      widget,
+      sequence: self.next_timer_sequence,
+    };
+    self.next_timer_sequence += 1;
+
+    let timer = Timer {
+      id,
+      deadline: (self.clock)().checked_add(duration),
+      interval: repeat.then_some(duration),
+    };
+    self.timers.push(timer);
+    Ok(id)
+  }
+
+  /// Cancel the given timer.
+  fn cancel_timer(&mut self, timer: TimerId) -> bool {
+    match self.timers.iter().position(|x| x.id == timer) {
+      Some(position) => {
+        let _timer = self.timers.remove(position);
+        true
+      },
+      None => false,
+    }
+  }
+
+  /// Spawn a task running the provided future.
+  fn spawn(&mut self, widget: Id, future: Pin<Box<dyn Future<Output = M>>>) -> TaskId {
+    let _idx = self.validate(widget);
+    let id = TaskId {
+      widget,
+      sequence: self.next_task_sequence,
+    };
+    self.next_task_sequence += 1;
+
+    let waker = TaskWaker {
+      // Newly spawned tasks have yet to be polled for the first time.
+      woken: AtomicBool::new(true),
+      driver: Arc::clone(&self.task_driver),
+    };
+    let task = Task {
+      id,
+      future,
+      waker: Arc::new(waker),
+    };
+    self.tasks.push(task);
+    id
+  }
+
+  /// Cancel the given task.
+  fn cancel_task(&mut self, task: TaskId) -> bool {
+    match self.tasks.iter().position(|x| x.id == task) {
+      Some(position) => {
+        let _task = self.tasks.remove(position);
+        true
+      },
+      None => false,
+    }
+  }
+
+  /// Queue up the provided message for delivery to the given widget.
+  fn post(&mut self, widget: Id, message: M) {
+    let _idx = self.validate(widget);
+    self.messages.push_back((widget, message));
+  }
+
+  /// Send the provided message up the widget hierarchy.
+  async fn send_up(&mut self, from: Id, mut message: M) -> Option<M> {
+    let idx = self.validate(from);
+    let mut parent_idx = self.widgets[idx].0.parent_idx;
+
+    while let Some(idx) = parent_idx {
+      let id = Id::new(idx, self);
+      let widget = Rc::clone(&self.widgets[idx].1);
+      message = widget.react(message, self).await?;
+
+      // The widget may have removed itself while reacting, in which
+      // case there is no one left to pass the message on to.
+      parent_idx = if self.widgets.contains(id) {
+        self.widgets[idx].0.parent_idx
+      } else {
+        None
+      };
+    }
+    Some(message)
+  }
+
+  /// Send a copy of the provided message to all widgets in a subtree.
+  async fn broadcast(&mut self, root: Id, message: M, opts: BroadcastOpts) -> bool
+  where
+    M: Clone,
+  {
+    let idx = self.validate(root);
+
+    // Widgets may get added or removed by message handlers, so we
+    // capture the receivers as `Id`s before delivering anything.
+    let mut receivers = Vec::new();
+    let mut to_visit = vec![Id::new(idx, self)];
+    while let Some(id) = to_visit.pop() {
+      receivers.push(id);
+      to_visit.extend(self.widgets[id.idx].0.children.iter().rev());
+    }
+
+    let mut consumed = false;
+    for id in receivers {
+      let idx = match self.try_validate(id) {
+        Ok(idx) => idx,
+        Err(..) => continue,
+      };
+      if opts.skip_hidden && !self.is_displayed(idx) {
+        continue
+      }
+
+      let widget = Rc::clone(&self.widgets[idx].1);
+      if widget.react(message.clone(), self).await.is_none() {
+        consumed = true;
+        if opts.stop_on_consume {
+          break
+        }
+      }
+    }
+    consumed
+  }
+
   async fn rehandle(&mut self, widget: Id, event: E) -> Option<E> {
     let idx = self.validate(widget);
     self.handle_event(idx, event).await
@@ -1,10 +1,12 @@
 // Copyright (C) 2018-2024 Daniel Mueller <deso@posteo.net>
 // SPDX-License-Identifier: GPL-3.0-or-later
 
+use std::any::Any;
 use std::any::TypeId;
 use std::fmt::Debug;
 
 use crate::Cap;
+use crate::Error;
 use crate::Handleable;
 use crate::MutCap;
 use crate::Object;
@@ -49,6 +51,35 @@ pub trait Widget<E, M>: Handleable<E, M> + Renderable + Object + Debug {
   {
     cap.data_mut(self.id()).downcast_mut::<D>().unwrap()
   }
+
+  /// Retrieve a reference to a widget's data, reporting an error if the
+  /// data is not of type `D`.
+  fn try_data<'c, D>(&self, cap: &'c dyn Cap) -> Result<&'c D, Error>
+  where
+    Self: Sized,
+    D: 'static,
+  {
+    let data = cap.try_data(self.id())?;
+    data.downcast_ref::<D>().ok_or(Error::DataTypeMismatch {
+      expected: TypeId::of::<D>(),
+      actual: Any::type_id(data),
+    })
+  }
+
+  /// Retrieve a mutable reference to a widget's data, reporting an
+  /// error if the data is not of type `D`.
+  fn try_data_mut<'c, D>(&self, cap: &'c mut dyn MutCap<E, M>) -> Result<&'c mut D, Error>
+  where
+    Self: Sized,
+    D: 'static,
+  {
+    let data = cap.try_data_mut(self.id())?;
+    let actual = Any::type_id(&*data);
+    data.downcast_mut::<D>().ok_or(Error::DataTypeMismatch {
+      expected: TypeId::of::<D>(),
+      actual,
+    })
+  }
 }
 
 impl<E, M> dyn Widget<E, M>
@@ -123,9 +123,11 @@ type ReactHandler = Handler<Rc<ReactFn>>;
 type RespondHandler = Handler<Rc<RespondFn>>;
 
 
+#[allow(unused)]
 #[derive(Debug)]
 pub struct TestWidgetData {
   event_handler: Option<EventHandler>,
+  capture_handler: Option<EventHandler>,
   react_handler: Option<ReactHandler>,
   respond_handler: Option<RespondHandler>,
 }
@@ -133,6 +135,7 @@ pub struct TestWidgetData {
 #[derive(Debug)]
 pub struct TestWidgetDataBuilder {
   event_handler: Option<EventHandler>,
+  capture_handler: Option<EventHandler>,
   react_handler: Option<ReactHandler>,
   respond_handler: Option<RespondHandler>,
 }
@@ -143,6 +146,7 @@ impl TestWidgetDataBuilder {
   pub fn new() -> Self {
     Self {
       event_handler: None,
+      capture_handler: None,
       react_handler: None,
       respond_handler: None,
     }
@@ -162,6 +166,20 @@ impl TestWidgetDataBuilder {
     self
   }
 
+  /// Set a handler for `Handleable::capture`.
+  pub fn capture_handler<F>(mut self, handler: F) -> Self
+  where
+    F: 'static
+      + for<'f> Fn(
+        Id,
+        &'f mut dyn MutCap<Event, Message>,
+        Event,
+      ) -> Pin<Box<dyn Future<Output = Option<Event>> + 'f>>,
+  {
+    self.capture_handler = Some(Handler(Rc::new(handler)));
+    self
+  }
+
   /// Set a handler for `Handleable::react`.
   pub fn react_handler<F>(mut self, handler: F) -> Self
   where
@@ -194,6 +212,7 @@ impl TestWidgetDataBuilder {
   pub fn build(self) -> Box<dyn Any> {
     let data = TestWidgetData {
       event_handler: self.event_handler,
+      capture_handler: self.capture_handler,
       react_handler: self.react_handler,
       respond_handler: self.respond_handler,
     };
@@ -201,12 +220,14 @@ impl TestWidgetDataBuilder {
   }
 }
 
+#[allow(unused)]
 #[derive(Debug, Widget)]
 #[gui(Event = Event, Message = Message)]
 pub struct TestWidget {
   id: Id,
 }
 
+#[allow(unused)]
 impl TestWidget {
   pub fn new(id: Id) -> Self {
     Self { id }
@@ -229,6 +250,17 @@ impl Handleable<Event, Message> for TestWidget {
     }
   }
 
+  async fn capture(&self, cap: &mut dyn MutCap<Event, Message>, event: Event) -> Option<Event> {
+    let data = self.data_mut::<TestWidgetData>(cap);
+    match &data.capture_handler {
+      Some(handler) => {
+        let handler = Rc::clone(handler);
+        handler(self.id, cap, event).await
+      },
+      None => Some(event),
+    }
+  }
+
   async fn react(&self, message: Message, cap: &mut dyn MutCap<Event, Message>) -> Option<Message> {
     let data = self.data_mut::<TestWidgetData>(cap);
     match &data.react_handler {
@@ -5,12 +5,15 @@
 
 mod common;
 
+use std::cell::RefCell;
 use std::future::Future;
 use std::pin::Pin;
+use std::rc::Rc;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::Ordering;
 
 use gui::Cap;
+use gui::EventFilterFn;
 use gui::EventHookFn;
 use gui::Id;
 use gui::MutCap;
@@ -276,7 +279,7 @@ async fn rehandle_from_event_hook() {
   let () = ui.focus(r);
   let _hook = ui.hook_events(
     r,
-    Some(&|widget, cap, event| {
+    Rc::new(|widget, cap, event| {
       Box::pin(async move {
         if let Some(event) = event {
           match event {
@@ -295,9 +298,9 @@ async fn rehandle_from_event_hook() {
   assert_eq!(result.unwrap_int(), 1);
 }
 
-/// Check that `MutCap::hook_events` behaves as expected.
+/// Check that `MutCap::unhook_events` behaves as expected.
 #[test]
-fn hook_events_return_value() {
+fn unhook_events_return_value() {
   let (mut ui, r) = Ui::new(
     || TestWidgetDataBuilder::new().build(),
     |id, _cap| Box::new(TestWidget::new(id)),
@@ -308,14 +311,18 @@ fn hook_events_return_value() {
     |id, _cap| Box::new(TestWidget::new(id)),
   );
 
-  assert!(ui.hook_events(w, None).is_none());
-  assert!(ui.hook_events(r, None).is_none());
-  assert!(ui.hook_events(w, Some(&emitting_event_hook)).is_none());
-  assert!(ui.hook_events(r, None).is_none());
-  assert!(ui.hook_events(r, Some(&emitting_event_hook)).is_none());
-  assert!(ui.hook_events(w, Some(&emitting_event_hook)).is_some());
-  assert!(ui.hook_events(r, Some(&emitting_event_hook)).is_some());
-  assert!(ui.hook_events(w, None).is_some());
+  let hook1 = ui.hook_events(w, Rc::new(emitting_event_hook));
+  let hook2 = ui.hook_events(w, Rc::new(emitting_event_hook));
+  let hook3 = ui.hook_events(r, Rc::new(emitting_event_hook));
+  assert_ne!(hook1, hook2);
+
+  assert!(ui.unhook_events(hook1).is_some());
+  assert!(ui.unhook_events(hook1).is_none());
+  assert!(ui.unhook_events(hook3).is_some());
+
+  // Removing the widget uninstalls all its hooks.
+  ui.remove_widget(w);
+  assert!(ui.unhook_events(hook2).is_none());
 }
 
 #[tokio::test]
@@ -358,7 +365,7 @@ async fn hook_events_handler() {
   );
 
   ui.focus(w1);
-  ui.hook_events(c1, Some(&count_event_hook));
+  let hook = ui.hook_events(c1, Rc::new(count_event_hook));
 
   assert_eq!(unsafe { HOOK_COUNT }, 0);
 
@@ -367,7 +374,7 @@ async fn hook_events_handler() {
 
   assert_eq!(unsafe { HOOK_COUNT }, 2);
 
-  ui.hook_events(c1, None);
+  let _hook_fn = ui.unhook_events(hook).unwrap();
 
   let event = Event::Key(' ');
   ui.handle(event).await.unwrap();
@@ -376,6 +383,44 @@ async fn hook_events_handler() {
 }
 
 
+/// Check that event hooks can be closures capturing state.
+#[tokio::test]
+async fn closure_event_hook() {
+  let (mut ui, r) = Ui::new(
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w = ui.add_ui_widget(
+    r,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+
+  let seen = Rc::new(RefCell::new(Vec::new()));
+  let hook = ui.hook_events(w, {
+    let seen = Rc::clone(&seen);
+    Rc::new(move |_widget, _cap, event| {
+      let () = seen.borrow_mut().push(event.copied());
+      Box::pin(async { None })
+    })
+  });
+
+  ui.focus(w);
+
+  let _ = ui.handle(Event::Key('a')).await;
+  assert_eq!(*seen.borrow(), vec![Some(Event::Key('a')), None]);
+
+  let hook_fn = ui.unhook_events(hook).unwrap();
+  let _ = ui.handle(Event::Key('b')).await;
+  assert_eq!(seen.borrow().len(), 2);
+
+  // The hook can be installed again after having been uninstalled.
+  let _hook = ui.hook_events(w, hook_fn);
+  let _ = ui.handle(Event::Key('c')).await;
+  assert_eq!(seen.borrow().len(), 4);
+}
+
+
 fn emitting_event_hook<'f>(
   _: &'f dyn Widget<Event, Message>,
   _cap: &'f mut dyn MutCap<Event, Message>,
@@ -420,7 +465,7 @@ async fn hook_emitted_events() {
   );
 
   ui.focus(w);
-  ui.hook_events(w, Some(&emitting_event_hook));
+  ui.hook_events(w, Rc::new(emitting_event_hook));
 
   let event = Event::Key('y');
   let result = ui.handle(event).await;
@@ -464,14 +509,100 @@ async fn hook_event_merging() {
 
   // We register two event hooks that emit different events that are not
   // actually mergeable by our definition. So we expect a panic.
-  ui.hook_events(w1, Some(&emitting_event_hook));
-  ui.hook_events(w2, Some(&different_emitting_event_hook));
+  ui.hook_events(w1, Rc::new(emitting_event_hook));
+  ui.hook_events(w2, Rc::new(different_emitting_event_hook));
 
   let event = Event::Key('y');
   let _ = ui.handle(event).await;
 }
 
 
+thread_local! {
+  static HOOK_ORDER: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+}
+
+fn recording_event_hook<'f>(
+  value: u64,
+  event: Option<&'f Event>,
+) -> Pin<Box<dyn Future<Output = Option<Event>> + 'f>> {
+  Box::pin(async move {
+    if event.is_some() {
+      let () = HOOK_ORDER.with(|order| order.borrow_mut().push(value));
+    }
+    None
+  })
+}
+
+fn recording_event_hook1<'f>(
+  _: &'f dyn Widget<Event, Message>,
+  _cap: &'f mut dyn MutCap<Event, Message>,
+  event: Option<&'f Event>,
+) -> Pin<Box<dyn Future<Output = Option<Event>> + 'f>> {
+  recording_event_hook(1, event)
+}
+
+fn recording_event_hook2<'f>(
+  _: &'f dyn Widget<Event, Message>,
+  _cap: &'f mut dyn MutCap<Event, Message>,
+  event: Option<&'f Event>,
+) -> Pin<Box<dyn Future<Output = Option<Event>> + 'f>> {
+  recording_event_hook(2, event)
+}
+
+fn recording_event_hook3<'f>(
+  _: &'f dyn Widget<Event, Message>,
+  _cap: &'f mut dyn MutCap<Event, Message>,
+  event: Option<&'f Event>,
+) -> Pin<Box<dyn Future<Output = Option<Event>> + 'f>> {
+  recording_event_hook(3, event)
+}
+
+/// Check that event hooks are invoked in order of descending priority,
+/// with ties being broken by installation order.
+#[tokio::test]
+async fn hook_priority_ordering() {
+  let (mut ui, r) = Ui::new(
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w1 = ui.add_ui_widget(
+    r,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w2 = ui.add_ui_widget(
+    r,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+
+  ui.focus(r);
+  // Install hooks in an order not matching widget creation order.
+  ui.hook_events(w2, Rc::new(recording_event_hook1));
+  let hook = ui.hook_events_with_priority(w1, Rc::new(recording_event_hook2), 0);
+  ui.hook_events_with_priority(r, Rc::new(recording_event_hook3), 10);
+
+  let _ = ui.handle(Event::Empty).await;
+  let order = HOOK_ORDER.with(RefCell::take);
+  assert_eq!(order, vec![3, 1, 2]);
+
+  // Hooks of equal priority run in installation order, even when
+  // installed by the same widget.
+  ui.hook_events_with_priority(w2, Rc::new(recording_event_hook1), 0);
+  ui.hook_events_with_priority(r, Rc::new(recording_event_hook3), -1);
+
+  let _ = ui.handle(Event::Empty).await;
+  let order = HOOK_ORDER.with(RefCell::take);
+  assert_eq!(order, vec![3, 1, 2, 1, 3]);
+
+  assert!(ui.unhook_events(hook).is_some());
+
+  let _ = ui.handle(Event::Empty).await;
+  let order = HOOK_ORDER.with(RefCell::take);
+  assert_eq!(order, vec![3, 1, 1, 3]);
+}
+
+
 /// Check that we can send a message from a hook.
 #[tokio::test]
 async fn hook_can_send_message() {
@@ -533,7 +664,7 @@ async fn hook_can_send_message() {
   assert_eq!(result, None);
   assert_eq!(unsafe { RECEIVED_VALUE }, 42);
 
-  ui.hook_events(c1, Some(&send_message_hook));
+  ui.hook_events(c1, Rc::new(send_message_hook));
 
   let result = ui.handle(Event::Int(3)).await;
   assert_eq!(result, None);
@@ -564,9 +695,10 @@ async fn hook_installation_event_handling() {
       || {
         TestWidgetDataBuilder::new()
           .event_handler(move |id, cap, _| {
+            let hook_fn = Rc::clone(&hook_fn);
             Box::pin(async move {
               if !INSTALLED.load(Ordering::Relaxed) {
-                let _prev_hook = cap.hook_events(id, Some(hook_fn));
+                let _hook = cap.hook_events(id, hook_fn);
                 INSTALLED.store(true, Ordering::Relaxed);
               }
               None
@@ -582,7 +714,7 @@ async fn hook_installation_event_handling() {
   }
 
   let event = Event::Key(' ');
-  let mut ui = setup_ui(&counting_event_hook);
+  let mut ui = setup_ui(Rc::new(counting_event_hook));
 
   // The first event will install the hook, but the hook function should
   // not get called on the post-hook path.
@@ -593,3 +725,279 @@ async fn hook_installation_event_handling() {
   let _result = ui.handle(event).await;
   assert_eq!(unsafe { COUNTING_HOOK_COUNT }, 2);
 }
+
+
+/// Check that removing a widget unregisters its event hook.
+#[tokio::test]
+async fn remove_hooked_widget() {
+  let (mut ui, r) = Ui::new(
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w = ui.add_ui_widget(
+    r,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+
+  ui.focus(r);
+  ui.hook_events(w, Rc::new(emitting_event_hook));
+  ui.remove_widget(w);
+
+  // With the hook gone the event should just be passed through.
+  let event = Event::Key('y');
+  let result = ui.handle(event).await;
+  assert_eq!(result.unwrap(), event);
+}
+
+
+fn capturing_handler(
+  _widget: Id,
+  _cap: &mut dyn MutCap<Event, Message>,
+  event: Event,
+) -> Pin<Box<dyn Future<Output = Option<Event>> + '_>> {
+  Box::pin(async move {
+    match event {
+      Event::Key('x') => None,
+      Event::Int(value) => Some(Event::Int(value * 10 + 1)),
+      _ => Some(event),
+    }
+  })
+}
+
+/// Check that ancestors of the focused widget can capture events,
+/// starting with the root.
+#[tokio::test]
+async fn event_capturing() {
+  let (mut ui, r) = Ui::new(
+    || {
+      TestWidgetDataBuilder::new()
+        .capture_handler(capturing_handler)
+        .build()
+    },
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let c1 = ui.add_ui_widget(
+    r,
+    || {
+      TestWidgetDataBuilder::new()
+        .capture_handler(|_id, _cap, event| {
+          Box::pin(async move { Some(Event::Int(event.unwrap_int() * 10 + 2)) })
+        })
+        .event_handler(incrementing_event_handler)
+        .build()
+    },
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w1 = ui.add_ui_widget(
+    c1,
+    || {
+      TestWidgetDataBuilder::new()
+        .capture_handler(|_id, _cap, _event| unreachable!())
+        .event_handler(move |_id, _cap, event| {
+          Box::pin(async move {
+            // The event should have been captured by `r` first and `c1`
+            // after that.
+            assert_eq!(event, Event::Int(12));
+            Some(event)
+          })
+        })
+        .build()
+    },
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+
+  ui.focus(w1);
+
+  let result = ui.handle(Event::Int(0)).await;
+  assert_eq!(result, Some(Event::Int(13)));
+}
+
+/// Check that a captured event can be consumed.
+#[tokio::test]
+async fn event_capturing_consumes() {
+  let (mut ui, r) = Ui::new(
+    || {
+      TestWidgetDataBuilder::new()
+        .capture_handler(capturing_handler)
+        .build()
+    },
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w1 = ui.add_ui_widget(
+    r,
+    || {
+      TestWidgetDataBuilder::new()
+        .event_handler(checking_event_handler)
+        .build()
+    },
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+
+  ui.focus(w1);
+
+  let result = ui.handle(Event::Key('x')).await;
+  assert_eq!(result, None);
+
+  // Other events make it through to the focused widget.
+  let result = ui.handle(Event::Key('y')).await;
+  assert_eq!(result, None);
+}
+
+
+fn rewriting_event_filter<'f>(
+  _: &'f dyn Widget<Event, Message>,
+  _cap: &'f mut dyn MutCap<Event, Message>,
+  event: Event,
+) -> Pin<Box<dyn Future<Output = Option<Event>> + 'f>> {
+  Box::pin(async move {
+    match event {
+      Event::Key('x') => None,
+      Event::Key('a') => Some(Event::Key('y')),
+      _ => Some(event),
+    }
+  })
+}
+
+/// Check that `MutCap::unfilter_events` behaves as expected.
+#[test]
+fn unfilter_events_return_value() {
+  let (mut ui, r) = Ui::new(
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w = ui.add_ui_widget(
+    r,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+
+  let filter1 = ui.filter_events(w, Rc::new(rewriting_event_filter));
+  let filter2 = ui.filter_events(w, Rc::new(rewriting_event_filter));
+  let filter3 = ui.filter_events(r, Rc::new(rewriting_event_filter));
+  assert_ne!(filter1, filter2);
+
+  assert!(ui.unfilter_events(filter1).is_some());
+  assert!(ui.unfilter_events(filter1).is_none());
+  assert!(ui.unfilter_events(filter3).is_some());
+
+  // Removing the widget uninstalls all its filters.
+  ui.remove_widget(w);
+  assert!(ui.unfilter_events(filter2).is_none());
+}
+
+/// Check that event filters can rewrite and discard events before they
+/// reach the focused widget.
+#[tokio::test]
+async fn event_filtering() {
+  let (mut ui, r) = Ui::new(
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w = ui.add_ui_widget(
+    r,
+    || {
+      TestWidgetDataBuilder::new()
+        .event_handler(checking_event_handler)
+        .build()
+    },
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+
+  ui.focus(w);
+  let filter = ui.filter_events(r, Rc::new(rewriting_event_filter));
+
+  // 'a' gets rewritten to 'y', which is what the widget expects.
+  let result = ui.handle(Event::Key('a')).await;
+  assert_eq!(result, None);
+
+  // 'x' is discarded and never reaches the widget.
+  let result = ui.handle(Event::Key('x')).await;
+  assert_eq!(result, None);
+
+  let _filter_fn = ui.unfilter_events(filter).unwrap();
+
+  let result = ui.handle(Event::Key('y')).await;
+  assert_eq!(result, None);
+}
+
+/// Check that discarded events are not seen by event hooks.
+#[tokio::test]
+async fn filtered_events_not_hooked() {
+  static HOOKED: AtomicBool = AtomicBool::new(false);
+
+  fn hook<'f>(
+    _: &'f dyn Widget<Event, Message>,
+    _cap: &'f mut dyn MutCap<Event, Message>,
+    _event: Option<&'f Event>,
+  ) -> Pin<Box<dyn Future<Output = Option<Event>> + 'f>> {
+    HOOKED.store(true, Ordering::Relaxed);
+    Box::pin(async { None })
+  }
+
+  let (mut ui, r) = Ui::new(
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+
+  ui.focus(r);
+  ui.hook_events(r, Rc::new(hook));
+  ui.filter_events(r, Rc::new(rewriting_event_filter));
+
+  let result = ui.handle(Event::Key('x')).await;
+  assert_eq!(result, None);
+  assert!(!HOOKED.load(Ordering::Relaxed));
+
+  let result = ui.handle(Event::Key('a')).await;
+  assert_eq!(result, Some(Event::Key('y')));
+  assert!(HOOKED.load(Ordering::Relaxed));
+}
+
+/// Check that removing a widget unregisters its event filter.
+#[tokio::test]
+async fn remove_filtering_widget() {
+  let (mut ui, r) = Ui::new(
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w = ui.add_ui_widget(
+    r,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+
+  ui.focus(r);
+  ui.filter_events(w, Rc::new(rewriting_event_filter));
+  ui.remove_widget(w);
+
+  let result = ui.handle(Event::Key('x')).await;
+  assert_eq!(result, Some(Event::Key('x')));
+}
+
+/// Check that multiple filters capturing state are invoked in the
+/// order in which they got installed.
+#[tokio::test]
+async fn closure_event_filters() {
+  let (mut ui, r) = Ui::new(
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+
+  let appending_filter = |digit| -> EventFilterFn<Event, Message> {
+    Rc::new(move |_widget, _cap, event| {
+      Box::pin(async move { Some(Event::Int(event.unwrap_int() * 10 + digit)) })
+    })
+  };
+
+  ui.focus(r);
+  let filter1 = ui.filter_events(r, appending_filter(1));
+  let _filter2 = ui.filter_events(r, appending_filter(2));
+  let _filter3 = ui.filter_events(r, appending_filter(3));
+
+  let result = ui.handle(Event::Int(0)).await;
+  assert_eq!(result, Some(Event::Int(123)));
+
+  let _filter_fn = ui.unfilter_events(filter1).unwrap();
+  let result = ui.handle(Event::Int(0)).await;
+  assert_eq!(result, Some(Event::Int(23)));
+}
@@ -0,0 +1,427 @@
+// Copyright (C) 2026 Daniel Mueller <deso@posteo.net>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Tests for focus related functionality.
+
+mod common;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use async_trait::async_trait;
+
+use gui::derive::Widget;
+use gui::Cap;
+use gui::Handleable;
+use gui::Id;
+use gui::MutCap;
+use gui::Ui;
+use gui::Widget;
+
+use crate::common::Event;
+use crate::common::Message;
+use crate::common::TestWidget;
+use crate::common::TestWidgetDataBuilder;
+
+
+/// Create a `Ui` with two containers with focusable widgets each.
+fn setup_ui() -> (Ui<Event, Message>, [Id; 6]) {
+  let new_data = || TestWidgetDataBuilder::new().build();
+  let (mut ui, r) = Ui::new(new_data, |id, _cap| Box::new(TestWidget::new(id)));
+  let c1 = ui.add_ui_widget(r, new_data, |id, _cap| Box::new(TestWidget::new(id)));
+  let w11 = ui.add_ui_widget(c1, new_data, |id, _cap| Box::new(TestWidget::new(id)));
+  let w12 = ui.add_ui_widget(c1, new_data, |id, _cap| Box::new(TestWidget::new(id)));
+  let c2 = ui.add_ui_widget(r, new_data, |id, _cap| Box::new(TestWidget::new(id)));
+  let w21 = ui.add_ui_widget(c2, new_data, |id, _cap| Box::new(TestWidget::new(id)));
+
+  ui.set_focusable(r, false);
+  ui.set_focusable(c1, false);
+  ui.set_focusable(c2, false);
+
+  (ui, [r, c1, w11, w12, c2, w21])
+}
+
+
+/// Check that `MutCap::focus_next` and `MutCap::focus_prev` walk the
+/// widget tree in document order.
+#[test]
+fn focus_traversal() {
+  let (mut ui, [r, c1, w11, w12, _c2, w21]) = setup_ui();
+
+  assert!(!ui.is_focusable(r));
+  assert!(!ui.is_focusable(c1));
+  assert!(ui.is_focusable(w11));
+
+  ui.focus_next();
+  assert!(ui.is_focused(w11));
+  ui.focus_next();
+  assert!(ui.is_focused(w12));
+  ui.focus_next();
+  assert!(ui.is_focused(w21));
+  // We should wrap around at the end.
+  ui.focus_next();
+  assert!(ui.is_focused(w11));
+
+  ui.focus_prev();
+  assert!(ui.is_focused(w21));
+  ui.focus_prev();
+  assert!(ui.is_focused(w12));
+  ui.focus_prev();
+  assert!(ui.is_focused(w11));
+}
+
+/// Check that focus traversal starts at either end of the chain when the
+/// focused widget is not part of it.
+#[test]
+fn focus_traversal_from_unfocusable() {
+  let (mut ui, [_r, c1, w11, _w12, _c2, w21]) = setup_ui();
+
+  ui.focus(c1);
+  ui.focus_next();
+  assert!(ui.is_focused(w11));
+
+  ui.focus(c1);
+  ui.focus_prev();
+  assert!(ui.is_focused(w21));
+}
+
+/// Check that hidden widgets are skipped during focus traversal.
+#[test]
+fn focus_traversal_skips_hidden() {
+  let (mut ui, [_r, c1, w11, w12, _c2, w21]) = setup_ui();
+
+  ui.hide(w12);
+  ui.focus(w11);
+  ui.focus_next();
+  assert!(ui.is_focused(w21));
+
+  ui.hide(c1);
+  ui.focus_next();
+  assert!(ui.is_focused(w21));
+}
+
+/// Check that tab indices take precedence over document order.
+#[test]
+fn focus_traversal_tab_index() {
+  let (mut ui, [_r, _c1, w11, w12, _c2, w21]) = setup_ui();
+
+  ui.set_tab_index(w21, Some(1));
+  ui.set_tab_index(w12, Some(2));
+  assert_eq!(ui.tab_index(w12), Some(2));
+  assert_eq!(ui.tab_index(w11), None);
+
+  ui.focus_next();
+  assert!(ui.is_focused(w21));
+  ui.focus_next();
+  assert!(ui.is_focused(w12));
+  ui.focus_next();
+  assert!(ui.is_focused(w11));
+  ui.focus_next();
+  assert!(ui.is_focused(w21));
+}
+
+/// Check that focus traversal does nothing if there is no focusable
+/// widget.
+#[test]
+fn focus_traversal_without_focusable() {
+  let (mut ui, [r, ..]) = setup_ui();
+
+  ui.hide(r);
+  ui.focus_next();
+  assert!(ui.focused().is_none());
+  ui.focus_prev();
+  assert!(ui.focused().is_none());
+}
+
+
+/// A log of focus change notifications.
+type FocusLog = Rc<RefCell<Vec<(Id, bool)>>>;
+
+/// A widget recording the focus change notifications it receives in
+/// the `FocusLog` it has as data.
+#[derive(Debug, Widget)]
+#[gui(Event = Event, Message = Message)]
+struct FocusWidget {
+  id: Id,
+}
+
+#[async_trait(?Send)]
+impl Handleable<Event, Message> for FocusWidget {
+  async fn handle(&self, cap: &mut dyn MutCap<Event, Message>, event: Event) -> Option<Event> {
+    match event {
+      Event::Key('n') => {
+        cap.focus_next();
+        None
+      },
+      Event::Key('h') => {
+        cap.hide(self.id);
+        None
+      },
+      _ => Some(event),
+    }
+  }
+
+  async fn focus_changed(&self, cap: &mut dyn MutCap<Event, Message>, gained: bool) {
+    let log = self.data::<FocusLog>(cap);
+    log.borrow_mut().push((self.id, gained));
+  }
+}
+
+/// Create a `Ui` with a root and two `FocusWidget` children.
+fn setup_focus_ui() -> (Ui<Event, Message>, FocusLog, [Id; 3]) {
+  let log = FocusLog::default();
+  let log_clone = Rc::clone(&log);
+  let (mut ui, r) = Ui::new(
+    move || Box::new(log_clone),
+    |id, _cap| Box::new(FocusWidget { id }),
+  );
+  let log_clone = Rc::clone(&log);
+  let w1 = ui.add_ui_widget(
+    r,
+    move || Box::new(log_clone),
+    |id, _cap| Box::new(FocusWidget { id }),
+  );
+  let log_clone = Rc::clone(&log);
+  let w2 = ui.add_ui_widget(
+    r,
+    move || Box::new(log_clone),
+    |id, _cap| Box::new(FocusWidget { id }),
+  );
+  ui.set_focusable(r, false);
+
+  (ui, log, [r, w1, w2])
+}
+
+
+/// Check that focus changes are reported to the affected widgets.
+#[tokio::test]
+async fn focus_change_notifications() {
+  let (mut ui, log, [_r, w1, w2]) = setup_focus_ui();
+
+  ui.focus(w1);
+  // Notifications are only delivered once explicitly requested.
+  assert!(log.borrow().is_empty());
+
+  ui.notify_focus_changes().await;
+  assert_eq!(*log.borrow(), vec![(w1, true)]);
+  log.borrow_mut().clear();
+
+  // Focusing the focused widget again does not trigger notifications.
+  ui.focus(w1);
+  ui.notify_focus_changes().await;
+  assert!(log.borrow().is_empty());
+
+  // The previously focused widget is notified first.
+  ui.focus(w2);
+  ui.notify_focus_changes().await;
+  assert_eq!(*log.borrow(), vec![(w1, false), (w2, true)]);
+}
+
+/// Check that focus changes performed while handling an event are
+/// reported before `Ui::handle` returns.
+#[tokio::test]
+async fn focus_change_notifications_from_handler() {
+  let (mut ui, log, [_r, w1, w2]) = setup_focus_ui();
+
+  ui.focus(w1);
+  let result = ui.handle(Event::Key('n')).await;
+  assert_eq!(result, None);
+  assert!(ui.is_focused(w2));
+  assert_eq!(*log.borrow(), vec![(w1, true), (w1, false), (w2, true)]);
+  log.borrow_mut().clear();
+
+  // Hiding the focused widget causes it to lose the focus to the
+  // previously focused one.
+  let result = ui.handle(Event::Key('h')).await;
+  assert_eq!(result, None);
+  assert!(ui.is_focused(w1));
+  assert_eq!(*log.borrow(), vec![(w2, false), (w1, true)]);
+}
+
+/// Check that removed widgets are not notified about focus changes.
+#[tokio::test]
+async fn focus_change_notifications_for_removed() {
+  let (mut ui, log, [_r, w1, w2]) = setup_focus_ui();
+
+  ui.focus(w1);
+  ui.focus(w2);
+  ui.remove_widget(w1);
+  ui.notify_focus_changes().await;
+  assert_eq!(*log.borrow(), vec![(w2, true)]);
+}
+
+
+/// Check that hiding the focused widget returns the focus to the
+/// previously focused one.
+#[test]
+fn focus_restored_on_hide() {
+  let (mut ui, [r, c1, w11, w12, c2, w21]) = setup_ui();
+
+  ui.focus(w11);
+  ui.focus(w21);
+  ui.focus(w12);
+
+  ui.hide(w12);
+  assert!(ui.is_focused(w21));
+
+  // Hiding the container of the focused widget has the same effect.
+  ui.hide(c2);
+  assert!(ui.is_focused(w11));
+
+  // Previously focused widgets that are hidden are skipped.
+  ui.show(w12);
+  ui.focus(w12);
+  ui.hide(w11);
+  ui.hide(w12);
+  assert!(ui.focused().is_none());
+
+  ui.show(w21);
+  ui.focus(w21);
+  ui.hide(r);
+  assert!(ui.focused().is_none());
+  assert!(!ui.is_displayed(c1));
+}
+
+/// Check that removing the focused widget returns the focus to the
+/// previously focused one.
+#[test]
+fn focus_restored_on_remove() {
+  let (mut ui, [_r, c1, w11, w12, _c2, w21]) = setup_ui();
+
+  ui.focus(w21);
+  ui.focus(w11);
+  ui.focus(w12);
+  ui.remove_widget(w12);
+  assert!(ui.is_focused(w11));
+
+  // Removed widgets are forgotten about.
+  ui.remove_widget(c1);
+  assert!(ui.is_focused(w21));
+}
+
+
+/// Check that focus changes are confined to the active modal scope.
+#[test]
+fn modal_scope_confines_focus() {
+  let (mut ui, [_r, c1, w11, w12, c2, w21]) = setup_ui();
+
+  ui.focus(w21);
+  assert_eq!(ui.modal_root(), None);
+
+  ui.push_modal(c1);
+  assert_eq!(ui.modal_root(), Some(c1));
+  // The focused widget was outside of the modal scope and so the
+  // scope's root got focused.
+  assert!(ui.is_focused(c1));
+
+  ui.focus(w21);
+  assert!(ui.is_focused(c1));
+
+  ui.focus(w12);
+  assert!(ui.is_focused(w12));
+
+  ui.focus_next();
+  assert!(ui.is_focused(w11));
+  ui.focus_next();
+  assert!(ui.is_focused(w12));
+
+  // Nested scopes replace outer ones while active.
+  ui.push_modal(w12);
+  ui.focus(w11);
+  assert!(ui.is_focused(w12));
+  assert_eq!(ui.pop_modal(), Some(w12));
+
+  // Hiding a focused widget only returns focus to widgets in scope.
+  ui.hide(w12);
+  assert!(ui.is_focused(w11));
+  ui.hide(w11);
+  assert!(ui.is_focused(c1));
+
+  // Once the modal dialog goes away, focus returns to where it was
+  // before.
+  ui.hide(c1);
+  assert!(ui.focused().is_none());
+  assert_eq!(ui.pop_modal(), Some(c1));
+  assert_eq!(ui.modal_root(), None);
+  assert!(ui.is_focused(w21));
+  assert!(ui.is_displayed(c2));
+  assert_eq!(ui.pop_modal(), None);
+}
+
+/// Check that removing the root of the active modal scope deactivates
+/// it.
+#[test]
+fn modal_scope_removal() {
+  let (mut ui, [_r, c1, w11, _w12, _c2, w21]) = setup_ui();
+
+  ui.focus(w21);
+  ui.push_modal(c1);
+  ui.focus(w11);
+  ui.remove_widget(c1);
+
+  assert_eq!(ui.modal_root(), None);
+  assert!(ui.is_focused(w21));
+}
+
+/// Check that popping a nested modal scope whose root is outside of
+/// the outer scope returns the focus to the outer scope.
+#[test]
+fn modal_scope_nested_sibling() {
+  let (mut ui, [_r, c1, w11, _w12, c2, _w21]) = setup_ui();
+
+  ui.focus(w11);
+  ui.push_modal(c1);
+  ui.push_modal(c2);
+  assert_eq!(ui.modal_root(), Some(c2));
+  assert!(ui.is_focused(c2));
+
+  assert_eq!(ui.pop_modal(), Some(c2));
+  assert_eq!(ui.modal_root(), Some(c1));
+  assert!(ui.is_focused(w11));
+}
+
+/// Check that moving the focused widget out of the active modal scope
+/// returns the focus to a widget inside of it.
+#[test]
+fn modal_scope_reparent() {
+  let (mut ui, [_r, c1, w11, w12, c2, _w21]) = setup_ui();
+
+  ui.focus(w12);
+  ui.push_modal(c1);
+  ui.focus(w11);
+
+  // Moving the focused widget within the scope keeps it focused.
+  ui.set_parent(w11, w12, 0).unwrap();
+  assert!(ui.is_focused(w11));
+
+  ui.set_parent(w11, c2, 0).unwrap();
+  assert!(ui.is_focused(w12));
+}
+
+/// Check that events do not bubble up past the root of the active
+/// modal scope.
+#[tokio::test]
+async fn modal_scope_confines_events() {
+  let new_data = || {
+    TestWidgetDataBuilder::new()
+      .event_handler(|_id, _cap, event| {
+        Box::pin(async move { Some(Event::Int(event.unwrap_int() + 1)) })
+      })
+      .build()
+  };
+  let (mut ui, r) = Ui::new(new_data, |id, _cap| Box::new(TestWidget::new(id)));
+  let c1 = ui.add_ui_widget(r, new_data, |id, _cap| Box::new(TestWidget::new(id)));
+  let w1 = ui.add_ui_widget(c1, new_data, |id, _cap| Box::new(TestWidget::new(id)));
+
+  ui.focus(w1);
+  let result = ui.handle(Event::Int(0)).await;
+  assert_eq!(result, Some(Event::Int(3)));
+
+  ui.push_modal(c1);
+  let result = ui.handle(Event::Int(0)).await;
+  assert_eq!(result, Some(Event::Int(2)));
+
+  let _ = ui.pop_modal();
+  let result = ui.handle(Event::Int(0)).await;
+  assert_eq!(result, Some(Event::Int(3)));
+}
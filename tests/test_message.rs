@@ -5,16 +5,29 @@
 
 mod common;
 
+use std::any::Any;
+use std::any::TypeId;
+use std::cell::RefCell;
 use std::future::Future;
 use std::pin::Pin;
+use std::rc::Rc;
 
 use async_trait::async_trait;
 
 use gui::derive::Widget;
+use gui::BroadcastOpts;
+use gui::Error;
 use gui::Handleable;
 use gui::Id;
 use gui::MutCap;
+use gui::Query;
+use gui::Receiver;
+use gui::Receives;
+use gui::Responder;
+use gui::Responds;
+use gui::Topic;
 use gui::Ui;
+use gui::Widget;
 
 use crate::common::Event;
 use crate::common::Message;
@@ -170,3 +183,577 @@ async fn call_message() {
   ui.call(w2, &mut message).await;
   assert_eq!(message.value, 2674);
 }
+
+
+/// Check that messages sent up the hierarchy are passed from ancestor
+/// to ancestor until handled.
+#[tokio::test]
+async fn send_message_up() {
+  static mut HANDLED_VALUE: u64 = 0;
+
+  let (mut ui, root) = Ui::new(
+    || {
+      TestWidgetDataBuilder::new()
+        .react_handler(|_id, message, _cap| {
+          Box::pin(async move {
+            if message.value > 10 {
+              unsafe { HANDLED_VALUE = message.value };
+              None
+            } else {
+              Some(message)
+            }
+          })
+        })
+        .build()
+    },
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let c1 = ui.add_ui_widget(
+    root,
+    || {
+      TestWidgetDataBuilder::new()
+        .react_handler(increment_message)
+        .build()
+    },
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w1 = ui.add_ui_widget(
+    c1,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+
+  let result = ui.send_up(w1, Message::new(42)).await;
+  assert_eq!(result, None);
+  assert_eq!(unsafe { HANDLED_VALUE }, 43);
+
+  // The root passes on the message but there is no one left to handle
+  // it.
+  let result = ui.send_up(w1, Message::new(1)).await;
+  assert_eq!(result, Some(Message::new(2)));
+
+  let result = ui.send_up(root, Message::new(1)).await;
+  assert_eq!(result, Some(Message::new(1)));
+}
+
+/// Check that posted messages are delivered only once drained, in
+/// order.
+#[tokio::test]
+async fn post_message() {
+  let log = Rc::new(RefCell::new(Vec::new()));
+  let log_clone = Rc::clone(&log);
+  let (mut ui, root) = Ui::new(
+    move || {
+      TestWidgetDataBuilder::new()
+        .react_handler(move |_id, message, _cap| {
+          let () = log_clone.borrow_mut().push(message.value);
+          Box::pin(async { None })
+        })
+        .build()
+    },
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w1 = ui.add_ui_widget(
+    root,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+
+  ui.post(root, Message::new(1));
+  ui.post(w1, Message::new(2));
+  ui.post(root, Message::new(3));
+  assert_eq!(*log.borrow(), Vec::<u64>::new());
+
+  // Messages for removed widgets are dropped.
+  ui.post(w1, Message::new(4));
+  ui.remove_widget(w1);
+
+  ui.drain_messages().await;
+  assert_eq!(log.take(), vec![1, 3]);
+}
+
+/// Check that messages posted while handling an event are delivered
+/// once the handling concluded.
+#[tokio::test]
+async fn post_message_from_handler() {
+  let log = Rc::new(RefCell::new(Vec::new()));
+  let log_clone = Rc::clone(&log);
+  let (mut ui, root) = Ui::new(
+    move || {
+      TestWidgetDataBuilder::new()
+        .react_handler(move |id, message, cap| {
+          let () = log_clone.borrow_mut().push(message.value);
+          // Messages posted during delivery are delivered as part of
+          // the same drain.
+          if message.value < 3 {
+            cap.post(id, Message::new(message.value + 1));
+          }
+          Box::pin(async { None })
+        })
+        .event_handler(|id, cap, _event| {
+          Box::pin(async move {
+            cap.post(id, Message::new(1));
+            Some(Event::Int(0))
+          })
+        })
+        .build()
+    },
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+
+  let log_clone = Rc::clone(&log);
+  let w1 = ui.add_ui_widget(
+    root,
+    || {
+      TestWidgetDataBuilder::new()
+        .event_handler(move |_id, _cap, event| {
+          let log = Rc::clone(&log_clone);
+          Box::pin(async move {
+            let () = log.borrow_mut().push(0);
+            Some(event)
+          })
+        })
+        .build()
+    },
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+
+  ui.focus(w1);
+
+  let result = ui.handle(Event::Empty).await;
+  assert_eq!(result, Some(Event::Int(0)));
+  assert_eq!(log.take(), vec![0, 1, 2, 3]);
+}
+
+/// A widget posting a message to itself when gaining the focus and
+/// passing the focus on when handling an event.
+#[derive(Debug, Widget)]
+#[gui(Event = Event, Message = Message)]
+struct FocusPostWidget {
+  id: Id,
+}
+
+#[async_trait(?Send)]
+impl Handleable<Event, Message> for FocusPostWidget {
+  async fn handle(&self, cap: &mut dyn MutCap<Event, Message>, _event: Event) -> Option<Event> {
+    cap.focus_next();
+    None
+  }
+
+  async fn react(&self, _message: Message, cap: &mut dyn MutCap<Event, Message>) -> Option<Message> {
+    let log = self.data::<MessageLog>(cap);
+    let () = log.borrow_mut().push(self.id);
+    None
+  }
+
+  async fn focus_changed(&self, cap: &mut dyn MutCap<Event, Message>, gained: bool) {
+    if gained {
+      cap.post(self.id, Message::new(0))
+    }
+  }
+}
+
+/// Check that messages posted by focus change handlers are delivered
+/// before `Ui::handle` returns.
+#[tokio::test]
+async fn post_message_from_focus_change() {
+  let log = MessageLog::default();
+  let new_data = || {
+    let log = Rc::clone(&log);
+    move || Box::new(log) as Box<dyn Any>
+  };
+  let new_widget = |id, _cap: &mut dyn MutCap<Event, Message>| {
+    Box::new(FocusPostWidget { id }) as Box<dyn Widget<Event, Message>>
+  };
+  let (mut ui, r) = Ui::new(new_data(), new_widget);
+  let w1 = ui.add_ui_widget(r, new_data(), new_widget);
+  let w2 = ui.add_ui_widget(r, new_data(), new_widget);
+  ui.set_focusable(r, false);
+
+  ui.focus(w1);
+  let result = ui.handle(Event::Empty).await;
+  assert_eq!(result, None);
+  assert!(ui.is_focused(w2));
+  assert_eq!(log.take(), vec![w1, w2]);
+}
+
+/// A log of widgets that received a message.
+type MessageLog = Rc<RefCell<Vec<Id>>>;
+
+/// Create a function creating widget data for a widget recording the
+/// messages it receives in the provided log and optionally consuming
+/// them.
+fn recording_data(log: &MessageLog, consume: bool) -> impl FnOnce() -> Box<dyn Any> {
+  let log = Rc::clone(log);
+  move || {
+    TestWidgetDataBuilder::new()
+      .react_handler(move |id, message, _cap| {
+        let () = log.borrow_mut().push(id);
+        Box::pin(async move { (!consume).then_some(message) })
+      })
+      .build()
+  }
+}
+
+/// Create a `Ui` with a container with two widgets, the first of which
+/// consumes messages, and another widget.
+fn setup_broadcast_ui() -> (Ui<Event, Message>, MessageLog, [Id; 5]) {
+  let log = MessageLog::default();
+  let new_widget = |id, _cap: &mut dyn MutCap<Event, Message>| {
+    Box::new(TestWidget::new(id)) as Box<dyn Widget<Event, Message>>
+  };
+  let (mut ui, r) = Ui::new(recording_data(&log, false), new_widget);
+  let c1 = ui.add_ui_widget(r, recording_data(&log, false), new_widget);
+  let w11 = ui.add_ui_widget(c1, recording_data(&log, true), new_widget);
+  let w12 = ui.add_ui_widget(c1, recording_data(&log, false), new_widget);
+  let c2 = ui.add_ui_widget(r, recording_data(&log, false), new_widget);
+
+  (ui, log, [r, c1, w11, w12, c2])
+}
+
+/// Check that broadcast messages reach all widgets in a subtree in
+/// pre-order.
+#[tokio::test]
+async fn broadcast_message() {
+  let (mut ui, log, [r, c1, w11, w12, c2]) = setup_broadcast_ui();
+
+  let opts = BroadcastOpts::default();
+  let consumed = ui.broadcast(r, Message::new(1), opts).await;
+  assert!(consumed);
+  assert_eq!(log.take(), vec![r, c1, w11, w12, c2]);
+
+  let consumed = ui.broadcast(c2, Message::new(1), opts).await;
+  assert!(!consumed);
+  assert_eq!(log.take(), vec![c2]);
+
+  // Children are visited in z-order.
+  ui.raise(w12);
+  let _ = ui.broadcast(c1, Message::new(1), opts).await;
+  assert_eq!(log.take(), vec![c1, w12, w11]);
+}
+
+/// Check that `BroadcastOpts` are honored.
+#[tokio::test]
+async fn broadcast_options() {
+  let (mut ui, log, [r, c1, w11, _w12, c2]) = setup_broadcast_ui();
+
+  let opts = BroadcastOpts {
+    stop_on_consume: true,
+    ..Default::default()
+  };
+  let consumed = ui.broadcast(r, Message::new(1), opts).await;
+  assert!(consumed);
+  assert_eq!(log.take(), vec![r, c1, w11]);
+
+  let opts = BroadcastOpts {
+    skip_hidden: true,
+    ..Default::default()
+  };
+  ui.hide(c1);
+  let consumed = ui.broadcast(r, Message::new(1), opts).await;
+  assert!(!consumed);
+  assert_eq!(log.take(), vec![r, c2]);
+}
+
+
+/// Check that published messages reach all subscribers of a topic.
+#[tokio::test]
+async fn publish_message() {
+  let (mut ui, log, [_r, c1, w11, w12, c2]) = setup_broadcast_ui();
+  let selection = Topic::named("selection");
+  let typed = Topic::of::<Message>();
+
+  assert!(ui.subscribe(c2, selection.clone()));
+  assert!(ui.subscribe(w11, selection.clone()));
+  assert!(ui.subscribe(w12, selection.clone()));
+  assert!(!ui.subscribe(c2, selection.clone()));
+  assert!(ui.subscribe(c2, typed.clone()));
+
+  // Subscribers receive the message in order of subscription,
+  // irrespective of whether they consume it.
+  let delivered = ui.publish(&selection, Message::new(1)).await;
+  assert_eq!(delivered, 3);
+  assert_eq!(log.take(), vec![c2, w11, w12]);
+
+  let delivered = ui.publish(&typed, Message::new(1)).await;
+  assert_eq!(delivered, 1);
+  assert_eq!(log.take(), vec![c2]);
+
+  let delivered = ui.publish(&Topic::named("other"), Message::new(1)).await;
+  assert_eq!(delivered, 0);
+  assert_eq!(log.take(), vec![]);
+
+  assert!(ui.unsubscribe(c2, &selection));
+  assert!(!ui.unsubscribe(c2, &selection));
+
+  // Subscriptions end when a widget is removed.
+  ui.remove_widget(c1);
+  let delivered = ui.publish(&selection, Message::new(1)).await;
+  assert_eq!(delivered, 0);
+  assert_eq!(log.take(), vec![]);
+
+  let delivered = ui.publish(&typed, Message::new(1)).await;
+  assert_eq!(delivered, 1);
+  assert_eq!(log.take(), vec![c2]);
+}
+
+/// A message type other than `Message`.
+#[derive(Debug, PartialEq)]
+struct Increment(u64);
+
+/// A widget receiving `Increment` messages and forwarding `Message`
+/// messages to another widget as `Increment` ones.
+#[derive(Debug, Widget)]
+#[gui(Event = Event, Message = Message)]
+struct TypedWidget {
+  id: Id,
+  next: Option<Id>,
+}
+
+#[async_trait(?Send)]
+impl Handleable<Event, Message> for TypedWidget {
+  async fn react(&self, message: Message, cap: &mut dyn MutCap<Event, Message>) -> Option<Message> {
+    let next = self.next.unwrap();
+    let response = cap.send_typed(next, Increment(message.value)).await;
+    response.unwrap().map(|Increment(value)| Message::new(value))
+  }
+}
+
+#[async_trait(?Send)]
+impl Receives<Increment, Event, Message> for TypedWidget {
+  async fn receive(
+    &self,
+    _cap: &mut dyn MutCap<Event, Message>,
+    message: Increment,
+  ) -> Option<Increment> {
+    Some(Increment(message.0 + 1))
+  }
+}
+
+/// Check that messages of types other than `Message` can be sent to
+/// widgets accepting them.
+#[tokio::test]
+async fn send_typed_message() {
+  let (mut ui, root) = Ui::new(
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w1 = ui.add_ui_widget(
+    root,
+    || Box::new(()),
+    |id, cap| {
+      cap
+        .add_receiver(id, Receiver::new::<TypedWidget, Increment>())
+        .unwrap();
+      Box::new(TypedWidget { id, next: None })
+    },
+  );
+  let w2 = ui.add_ui_widget(
+    root,
+    || Box::new(()),
+    |id, _cap| Box::new(TypedWidget { id, next: Some(w1) }),
+  );
+
+  let result = ui.send_typed(w1, Increment(41)).await;
+  assert_eq!(result, Ok(Some(Increment(42))));
+
+  // Widgets can send typed messages as well.
+  let result = ui.send(w2, Message::new(1)).await;
+  assert_eq!(result, Some(Message::new(2)));
+
+  // `w2` has not registered a receiver.
+  let result = ui.send_typed(w2, Increment(1)).await;
+  let expected = Error::MessageNotAccepted {
+    message_type: TypeId::of::<Increment>(),
+  };
+  assert_eq!(result, Err(expected));
+
+  let result = ui.send_typed(w1, 42u64).await;
+  let expected = Error::MessageNotAccepted {
+    message_type: TypeId::of::<u64>(),
+  };
+  assert_eq!(result, Err(expected));
+
+  ui.remove_widget(w1);
+  let result = ui.send_typed(w1, Increment(1)).await;
+  assert_eq!(result, Err(Error::InvalidId));
+}
+
+/// Check that receivers created for a different widget type are
+/// rejected.
+#[tokio::test]
+async fn receiver_widget_type_mismatch() {
+  let (mut ui, root) = Ui::new(
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  // While a widget is being constructed its type cannot be checked.
+  let w = ui.add_ui_widget(
+    root,
+    || TestWidgetDataBuilder::new().build(),
+    |id, cap| {
+      cap
+        .add_receiver(id, Receiver::new::<TypedWidget, Increment>())
+        .unwrap();
+      Box::new(TestWidget::new(id))
+    },
+  );
+
+  let expected = Error::WidgetTypeMismatch {
+    expected: TypeId::of::<TypedWidget>(),
+    actual: TypeId::of::<TestWidget>(),
+  };
+  let result = ui.send_typed(w, Increment(1)).await;
+  assert_eq!(result, Err(expected));
+
+  let result = ui.add_receiver(root, Receiver::new::<TypedWidget, Increment>());
+  assert_eq!(result, Err(expected));
+  let result = ui.send_typed(root, Increment(1)).await;
+  let expected = Error::MessageNotAccepted {
+    message_type: TypeId::of::<Increment>(),
+  };
+  assert_eq!(result, Err(expected));
+
+  ui.remove_widget(w);
+  let result = ui.add_receiver(w, Receiver::new::<TypedWidget, Increment>());
+  assert_eq!(result, Err(Error::InvalidId));
+}
+
+
+/// A query for the current selection of a widget.
+#[derive(Debug)]
+struct GetSelection;
+
+impl Query for GetSelection {
+  type Reply = Option<usize>;
+}
+
+/// A widget answering `GetSelection` queries and forwarding them when
+/// reacting to a `Message`.
+#[derive(Debug, Widget)]
+#[gui(Event = Event, Message = Message)]
+struct SelectionWidget {
+  id: Id,
+  selection: Option<usize>,
+  other: Option<Id>,
+}
+
+#[async_trait(?Send)]
+impl Handleable<Event, Message> for SelectionWidget {
+  async fn react(&self, _message: Message, cap: &mut dyn MutCap<Event, Message>) -> Option<Message> {
+    let other = self.other.unwrap();
+    let selection = cap.request(other, GetSelection).await.unwrap();
+    selection.map(|selection| Message::new(selection as u64))
+  }
+}
+
+#[async_trait(?Send)]
+impl Responds<GetSelection, Event, Message> for SelectionWidget {
+  async fn respond_to(
+    &self,
+    _cap: &mut dyn MutCap<Event, Message>,
+    _query: GetSelection,
+  ) -> Option<usize> {
+    self.selection
+  }
+}
+
+/// Check that queries are answered with replies of the query specific
+/// type.
+#[tokio::test]
+async fn request_typed_reply() {
+  let (mut ui, root) = Ui::new(
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w1 = ui.add_ui_widget(
+    root,
+    || Box::new(()),
+    |id, cap| {
+      cap
+        .add_responder(id, Responder::new::<SelectionWidget, GetSelection>())
+        .unwrap();
+      Box::new(SelectionWidget {
+        id,
+        selection: Some(3),
+        other: None,
+      })
+    },
+  );
+  let w2 = ui.add_ui_widget(
+    root,
+    || Box::new(()),
+    |id, _cap| {
+      Box::new(SelectionWidget {
+        id,
+        selection: None,
+        other: Some(w1),
+      })
+    },
+  );
+
+  let reply = ui.request(w1, GetSelection).await;
+  assert_eq!(reply, Ok(Some(3)));
+
+  // Widgets can issue queries as well.
+  let result = ui.send(w2, Message::new(0)).await;
+  assert_eq!(result, Some(Message::new(3)));
+
+  // `w2` has not registered a responder.
+  let reply = ui.request(w2, GetSelection).await;
+  let expected = Error::QueryNotAccepted {
+    query_type: TypeId::of::<GetSelection>(),
+  };
+  assert_eq!(reply, Err(expected));
+
+  // Responders are distinct from receivers.
+  let result = ui.send_typed(w1, GetSelection).await.map(|_| ());
+  let expected = Error::MessageNotAccepted {
+    message_type: TypeId::of::<GetSelection>(),
+  };
+  assert_eq!(result, Err(expected));
+
+  // Responders created for a different widget type are rejected.
+  let result = ui.add_responder(root, Responder::new::<SelectionWidget, GetSelection>());
+  let expected = Error::WidgetTypeMismatch {
+    expected: TypeId::of::<SelectionWidget>(),
+    actual: TypeId::of::<TestWidget>(),
+  };
+  assert_eq!(result, Err(expected));
+
+  ui.remove_widget(w1);
+  let reply = ui.request(w1, GetSelection).await;
+  assert_eq!(reply, Err(Error::InvalidId));
+}
+
+/// Check that queries to a widget with a responder created for a
+/// different widget type are rejected.
+#[tokio::test]
+async fn responder_widget_type_mismatch() {
+  let (mut ui, root) = Ui::new(
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  // While a widget is being constructed its type cannot be checked.
+  let w = ui.add_ui_widget(
+    root,
+    || TestWidgetDataBuilder::new().build(),
+    |id, cap| {
+      cap
+        .add_responder(id, Responder::new::<SelectionWidget, GetSelection>())
+        .unwrap();
+      Box::new(TestWidget::new(id))
+    },
+  );
+
+  let reply = ui.request(w, GetSelection).await;
+  let expected = Error::WidgetTypeMismatch {
+    expected: TypeId::of::<SelectionWidget>(),
+    actual: TypeId::of::<TestWidget>(),
+  };
+  assert_eq!(reply, Err(expected));
+
+  ui.remove_widget(w);
+  let result = ui.add_responder(w, Responder::new::<SelectionWidget, GetSelection>());
+  assert_eq!(result, Err(Error::InvalidId));
+}
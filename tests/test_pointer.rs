@@ -0,0 +1,532 @@
+// Copyright (C) 2026 Daniel Mueller <deso@posteo.net>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Tests for pointer related functionality.
+
+mod common;
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use async_trait::async_trait;
+
+use gui::derive::Widget;
+use gui::BBox;
+use gui::Cap;
+use gui::Handleable;
+use gui::Id;
+use gui::MutCap;
+use gui::Object;
+use gui::Renderable;
+use gui::Renderer;
+use gui::Ui;
+use gui::Widget;
+
+use crate::common::Event;
+use crate::common::Message;
+
+
+/// A log of pointer related notifications received by widgets.
+type PointerLog = Rc<RefCell<Vec<(Id, &'static str)>>>;
+
+/// The data of a `PointerWidget`.
+#[derive(Debug)]
+struct PointerData {
+  /// The digit appended to `Event::Int` events handled by the widget.
+  digit: u64,
+  /// Whether the widget accepts `&'static str` drag payloads.
+  accepts: bool,
+  /// Whether the widget posts a message to itself when notified about
+  /// the pointer entering it or receiving a drag payload.
+  post: bool,
+  /// The log to record notifications in.
+  log: PointerLog,
+}
+
+/// A widget recording pointer related notifications in the log it
+/// has as part of its data.
+#[derive(Debug, Widget)]
+#[gui(Event = Event, Message = Message)]
+struct PointerWidget {
+  id: Id,
+}
+
+#[async_trait(?Send)]
+impl Handleable<Event, Message> for PointerWidget {
+  async fn handle(&self, cap: &mut dyn MutCap<Event, Message>, event: Event) -> Option<Event> {
+    let digit = self.data::<PointerData>(cap).digit;
+    Some(Event::Int(event.unwrap_int() * 10 + digit))
+  }
+
+  async fn react(&self, _message: Message, cap: &mut dyn MutCap<Event, Message>) -> Option<Message> {
+    let log = &self.data::<PointerData>(cap).log;
+    let () = log.borrow_mut().push((self.id, "message"));
+    None
+  }
+
+  async fn hover_changed(&self, cap: &mut dyn MutCap<Event, Message>, entered: bool) {
+    let data = self.data::<PointerData>(cap);
+    let entry = if entered { "enter" } else { "leave" };
+    let () = data.log.borrow_mut().push((self.id, entry));
+
+    if entered && data.post {
+      cap.post(self.id, Message::new(0))
+    }
+  }
+
+  fn accepts_drop(&self, cap: &dyn Cap, _source: Id, payload: &dyn Any) -> bool {
+    self.data::<PointerData>(cap).accepts && payload.is::<&'static str>()
+  }
+
+  async fn dropped(&self, cap: &mut dyn MutCap<Event, Message>, _source: Id, payload: Box<dyn Any>) {
+    let log = &self.data::<PointerData>(cap).log;
+    let payload = payload.downcast::<&'static str>().unwrap();
+    let () = log.borrow_mut().push((self.id, *payload));
+
+    if self.data::<PointerData>(cap).post {
+      cap.post(self.id, Message::new(0))
+    }
+  }
+}
+
+/// Make the widget with the given `Id` accept drag payloads.
+fn accept_drops(ui: &mut Ui<Event, Message>, widget: Id) {
+  let data = ui.data_mut(widget).downcast_mut::<PointerData>().unwrap();
+  data.accepts = true;
+}
+
+
+/// A renderer handing out predefined bounding boxes to the children
+/// of widgets.
+#[derive(Debug, Default)]
+struct LayoutRenderer {
+  inner: HashMap<Id, BBox>,
+}
+
+impl Renderer for LayoutRenderer {
+  fn renderable_area(&self) -> BBox {
+    BBox {
+      x: 0,
+      y: 0,
+      w: 100,
+      h: 100,
+    }
+  }
+
+  fn render(&self, object: &dyn Renderable, _cap: &dyn Cap, bbox: BBox) -> BBox {
+    let widget = object.downcast_ref::<PointerWidget>().unwrap();
+    self.inner.get(&widget.id()).copied().unwrap_or(bbox)
+  }
+}
+
+
+/// Create a `Ui` with a root, two containers covering each other, and
+/// a widget inside the first container, along with a renderer laying
+/// them out.
+///
+/// Each widget appends a different digit to `Event::Int` events it
+/// handles, making the path an event took visible.
+fn setup_ui() -> (Ui<Event, Message>, [Id; 4], LayoutRenderer, PointerLog) {
+  let log = PointerLog::default();
+  let new_data = |digit| {
+    let log = Rc::clone(&log);
+    move || {
+      let data = PointerData {
+        digit,
+        accepts: false,
+        post: false,
+        log,
+      };
+      Box::new(data) as Box<dyn Any>
+    }
+  };
+  let new_widget = |id, _cap: &mut dyn MutCap<Event, Message>| {
+    Box::new(PointerWidget { id }) as Box<dyn Widget<Event, Message>>
+  };
+
+  let (mut ui, r) = Ui::new(new_data(1), new_widget);
+  let c1 = ui.add_ui_widget(r, new_data(2), new_widget);
+  let w11 = ui.add_ui_widget(c1, new_data(3), new_widget);
+  let c2 = ui.add_ui_widget(r, new_data(4), new_widget);
+
+  let mut renderer = LayoutRenderer::default();
+  let _ = renderer.inner.insert(
+    r,
+    BBox {
+      x: 10,
+      y: 10,
+      w: 80,
+      h: 80,
+    },
+  );
+  let _ = renderer.inner.insert(
+    c1,
+    BBox {
+      x: 20,
+      y: 20,
+      w: 10,
+      h: 10,
+    },
+  );
+
+  (ui, [r, c1, w11, c2], renderer, log)
+}
+
+
+/// Check that `BBox::contains` works as expected.
+#[test]
+fn bbox_contains() {
+  let bbox = BBox {
+    x: 1,
+    y: 2,
+    w: 3,
+    h: 4,
+  };
+
+  assert!(bbox.contains(1, 2));
+  assert!(bbox.contains(3, 5));
+  assert!(!bbox.contains(0, 2));
+  assert!(!bbox.contains(4, 2));
+  assert!(!bbox.contains(1, 6));
+
+  let bbox = BBox {
+    x: u16::MAX,
+    y: u16::MAX,
+    w: u16::MAX,
+    h: u16::MAX,
+  };
+  assert!(bbox.contains(u16::MAX, u16::MAX));
+  assert!(!BBox::default().contains(0, 0));
+}
+
+/// Check that events are routed to the top-most widget containing a
+/// point.
+#[tokio::test]
+async fn hit_test_routing() {
+  let (mut ui, [_r, c1, _w11, c2], renderer, _log) = setup_ui();
+
+  // Nothing got rendered yet, so nothing can be hit.
+  let result = ui.handle_at(25, 25, Event::Int(0)).await;
+  assert_eq!(result, Some(Event::Int(0)));
+
+  ui.raise(c1);
+  ui.render(&renderer);
+
+  let result = ui.handle_at(25, 25, Event::Int(0)).await;
+  assert_eq!(result, Some(Event::Int(321)));
+  let result = ui.handle_at(50, 50, Event::Int(0)).await;
+  assert_eq!(result, Some(Event::Int(21)));
+  let result = ui.handle_at(5, 5, Event::Int(0)).await;
+  assert_eq!(result, Some(Event::Int(1)));
+  let result = ui.handle_at(100, 100, Event::Int(0)).await;
+  assert_eq!(result, Some(Event::Int(0)));
+
+  // With the second container on top, it covers the first one entirely.
+  ui.raise(c2);
+
+  let result = ui.handle_at(25, 25, Event::Int(0)).await;
+  assert_eq!(result, Some(Event::Int(41)));
+}
+
+/// Check that hidden widgets are not considered during hit-testing.
+#[tokio::test]
+async fn hit_test_hidden() {
+  let (mut ui, [_r, c1, w11, _c2], renderer, _log) = setup_ui();
+
+  ui.raise(c1);
+  ui.render(&renderer);
+  ui.hide(w11);
+
+  let result = ui.handle_at(25, 25, Event::Int(0)).await;
+  assert_eq!(result, Some(Event::Int(21)));
+
+  ui.hide(c1);
+
+  let result = ui.handle_at(25, 25, Event::Int(0)).await;
+  assert_eq!(result, Some(Event::Int(41)));
+
+  // Showing the widget again is not enough; it has to be rendered.
+  ui.render(&renderer);
+  ui.show(w11);
+
+  let result = ui.handle_at(25, 25, Event::Int(0)).await;
+  assert_eq!(result, Some(Event::Int(41)));
+
+  ui.render(&renderer);
+
+  let result = ui.handle_at(25, 25, Event::Int(0)).await;
+  assert_eq!(result, Some(Event::Int(321)));
+}
+
+/// Check that hit-testing is confined to the active modal scope.
+#[tokio::test]
+async fn hit_test_modal() {
+  let (mut ui, [_r, c1, _w11, _c2], renderer, _log) = setup_ui();
+
+  ui.raise(c1);
+  ui.render(&renderer);
+  ui.push_modal(c1);
+
+  let result = ui.handle_at(5, 5, Event::Int(0)).await;
+  assert_eq!(result, Some(Event::Int(0)));
+  // Events do not bubble up past the modal root either.
+  let result = ui.handle_at(25, 25, Event::Int(0)).await;
+  assert_eq!(result, Some(Event::Int(32)));
+}
+
+/// Check that the hovered widget is tracked and notified about the
+/// pointer entering and leaving it.
+#[tokio::test]
+async fn hover_tracking() {
+  let (mut ui, [r, c1, w11, _c2], renderer, log) = setup_ui();
+
+  ui.raise(c1);
+  ui.render(&renderer);
+  assert_eq!(ui.hovered(), None);
+
+  ui.pointer_moved(25, 25).await;
+  assert_eq!(ui.hovered(), Some(w11));
+  assert_eq!(log.take(), vec![(w11, "enter")]);
+
+  // Moving within the same widget does not cause any notifications.
+  ui.pointer_moved(26, 27).await;
+  assert_eq!(log.take(), vec![]);
+
+  ui.pointer_moved(50, 50).await;
+  assert_eq!(ui.hovered(), Some(c1));
+  assert_eq!(log.take(), vec![(w11, "leave"), (c1, "enter")]);
+
+  ui.pointer_moved(5, 5).await;
+  assert_eq!(ui.hovered(), Some(r));
+  assert_eq!(log.take(), vec![(c1, "leave"), (r, "enter")]);
+
+  ui.pointer_moved(100, 100).await;
+  assert_eq!(ui.hovered(), None);
+  assert_eq!(log.take(), vec![(r, "leave")]);
+}
+
+/// Check that removing the hovered widget resets the hover state.
+#[tokio::test]
+async fn hover_removed_widget() {
+  let (mut ui, [_r, c1, w11, _c2], renderer, log) = setup_ui();
+
+  ui.raise(c1);
+  ui.render(&renderer);
+
+  ui.pointer_moved(25, 25).await;
+  assert_eq!(log.take(), vec![(w11, "enter")]);
+
+  ui.remove_widget(c1);
+  assert_eq!(ui.hovered(), None);
+
+  // The removed widget is not notified about the pointer leaving it.
+  ui.render(&renderer);
+  ui.pointer_moved(25, 25).await;
+  assert_eq!(log.take().len(), 1);
+}
+
+/// Check that hiding the hovered widget resets the hover state.
+#[tokio::test]
+async fn hover_hidden_widget() {
+  let (mut ui, [_r, c1, w11, _c2], renderer, log) = setup_ui();
+
+  ui.raise(c1);
+  ui.render(&renderer);
+
+  ui.pointer_moved(25, 25).await;
+  assert_eq!(log.take(), vec![(w11, "enter")]);
+
+  ui.hide(c1);
+  assert_eq!(ui.hovered(), None);
+  assert_eq!(log.take(), vec![]);
+
+  // Once shown again, the widget is first left and then entered anew.
+  ui.show(c1);
+  ui.pointer_moved(25, 25).await;
+  assert_eq!(ui.hovered(), Some(w11));
+  assert_eq!(log.take(), vec![(w11, "leave"), (w11, "enter")]);
+}
+
+/// Check that messages posted by hover change handlers are delivered
+/// before `Ui::pointer_moved` returns.
+#[tokio::test]
+async fn hover_post_message() {
+  let (mut ui, [_r, c1, w11, _c2], renderer, log) = setup_ui();
+
+  ui.raise(c1);
+  ui.render(&renderer);
+
+  let data = ui.data_mut(w11).downcast_mut::<PointerData>().unwrap();
+  data.post = true;
+
+  ui.pointer_moved(25, 25).await;
+  assert_eq!(log.take(), vec![(w11, "enter"), (w11, "message")]);
+}
+
+/// Check that pointer events are routed to the widget grabbing the
+/// pointer.
+#[tokio::test]
+async fn pointer_grab() {
+  let (mut ui, [r, c1, w11, c2], renderer, log) = setup_ui();
+
+  ui.raise(c1);
+  ui.render(&renderer);
+  ui.pointer_moved(25, 25).await;
+  assert_eq!(log.take(), vec![(w11, "enter")]);
+
+  ui.grab_pointer(c2);
+  assert_eq!(ui.pointer_grab(), Some(c2));
+
+  let result = ui.handle_at(25, 25, Event::Int(0)).await;
+  assert_eq!(result, Some(Event::Int(41)));
+  let result = ui.handle_at(100, 100, Event::Int(0)).await;
+  assert_eq!(result, Some(Event::Int(41)));
+
+  // The grabbing widget is considered hovered.
+  ui.pointer_moved(5, 5).await;
+  assert_eq!(ui.hovered(), Some(c2));
+  assert_eq!(log.take(), vec![(w11, "leave"), (c2, "enter")]);
+
+  ui.release_pointer();
+  assert_eq!(ui.pointer_grab(), None);
+
+  let result = ui.handle_at(5, 5, Event::Int(0)).await;
+  assert_eq!(result, Some(Event::Int(1)));
+  ui.pointer_moved(5, 5).await;
+  assert_eq!(log.take(), vec![(c2, "leave"), (r, "enter")]);
+}
+
+/// Check that a pointer grab is released once the grabbing widget is
+/// no longer displayed or removed.
+#[tokio::test]
+async fn pointer_grab_release() {
+  let (mut ui, [r, c1, w11, c2], renderer, _log) = setup_ui();
+
+  ui.raise(c1);
+  ui.render(&renderer);
+
+  ui.grab_pointer(w11);
+  ui.hide(r);
+  ui.show(r);
+  assert_eq!(ui.pointer_grab(), None);
+
+  // Hiding a widget other than the grabbing one does not affect the
+  // grab.
+  ui.grab_pointer(w11);
+  ui.hide(c2);
+  assert_eq!(ui.pointer_grab(), Some(w11));
+
+  // Moving the grabbing widget below a hidden one releases the grab as
+  // well.
+  ui.set_parent(w11, c2, 0).unwrap();
+  assert_eq!(ui.pointer_grab(), None);
+
+  // A widget that is not displayed cannot grab the pointer.
+  ui.grab_pointer(w11);
+  assert_eq!(ui.pointer_grab(), None);
+
+  ui.set_parent(w11, c1, 0).unwrap();
+  ui.grab_pointer(w11);
+  ui.remove_widget(c1);
+  assert_eq!(ui.pointer_grab(), None);
+
+  let result = ui.handle_at(25, 25, Event::Int(0)).await;
+  assert_eq!(result, Some(Event::Int(1)));
+}
+
+/// Check that a grab by a widget outside the active modal scope is
+/// ineffective.
+#[tokio::test]
+async fn pointer_grab_modal() {
+  let (mut ui, [_r, c1, _w11, c2], renderer, _log) = setup_ui();
+
+  ui.raise(c1);
+  ui.render(&renderer);
+  ui.grab_pointer(c2);
+  ui.push_modal(c1);
+
+  let result = ui.handle_at(50, 50, Event::Int(0)).await;
+  assert_eq!(result, Some(Event::Int(2)));
+
+  let _ = ui.pop_modal();
+
+  let result = ui.handle_at(50, 50, Event::Int(0)).await;
+  assert_eq!(result, Some(Event::Int(41)));
+}
+
+/// Check that a dragged payload is transferred to the widget accepting
+/// it.
+#[tokio::test]
+async fn drag_and_drop() {
+  let (mut ui, [_r, c1, w11, c2], renderer, log) = setup_ui();
+
+  ui.raise(c1);
+  ui.render(&renderer);
+  accept_drops(&mut ui, c1);
+  accept_drops(&mut ui, c2);
+
+  assert_eq!(ui.drop_target(25, 25), None);
+  assert_eq!(ui.drop_at(25, 25).await, None);
+
+  ui.start_drag(w11, Box::new("item"));
+  assert_eq!(ui.drag_source(), Some(w11));
+
+  // `w11` does not accept the payload, but its parent does.
+  assert_eq!(ui.drop_target(25, 25), Some(c1));
+  assert_eq!(ui.drop_target(5, 5), None);
+
+  // Drop targets are determined irrespective of any pointer grab.
+  ui.grab_pointer(w11);
+  assert_eq!(ui.drop_at(50, 50).await, Some(c1));
+  assert_eq!(log.take(), vec![(c1, "item")]);
+  assert_eq!(ui.drag_source(), None);
+
+  // Payloads of unexpected types are not accepted.
+  ui.start_drag(w11, Box::new(42u64));
+  assert_eq!(ui.drop_at(50, 50).await, None);
+  assert_eq!(ui.drag_source(), None);
+  assert_eq!(log.take(), vec![]);
+}
+
+/// Check that messages posted by the widget receiving a drag payload
+/// are delivered before `Ui::drop_at` returns.
+#[tokio::test]
+async fn drop_post_message() {
+  let (mut ui, [_r, c1, w11, _c2], renderer, log) = setup_ui();
+
+  ui.raise(c1);
+  ui.render(&renderer);
+  accept_drops(&mut ui, c1);
+
+  let data = ui.data_mut(c1).downcast_mut::<PointerData>().unwrap();
+  data.post = true;
+
+  ui.start_drag(w11, Box::new("item"));
+  assert_eq!(ui.drop_at(25, 25).await, Some(c1));
+  assert_eq!(log.take(), vec![(c1, "item"), (c1, "message")]);
+}
+
+/// Check that drag operations can be canceled.
+#[test]
+fn drag_cancel() {
+  let (mut ui, [r, c1, w11, c2], _renderer, _log) = setup_ui();
+
+  assert!(ui.cancel_drag().is_none());
+
+  ui.start_drag(w11, Box::new("item"));
+  let payload = ui.cancel_drag().unwrap();
+  assert_eq!(*payload.downcast::<&'static str>().unwrap(), "item");
+  assert_eq!(ui.drag_source(), None);
+
+  // Removing the source widget cancels the drag operation.
+  ui.start_drag(w11, Box::new("item"));
+  ui.remove_widget(c1);
+  assert_eq!(ui.drag_source(), None);
+
+  // Removing any other widget does not.
+  ui.start_drag(r, Box::new("item"));
+  ui.remove_widget(c2);
+  assert_eq!(ui.drag_source(), Some(r));
+}
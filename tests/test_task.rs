@@ -0,0 +1,313 @@
+// Copyright (C) 2026 Daniel Mueller <deso@posteo.net>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Tests for widget task related functionality.
+
+mod common;
+
+use std::any::Any;
+use std::cell::RefCell;
+use std::future::pending;
+use std::future::poll_fn;
+use std::future::Future;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::task::Context;
+use std::task::Poll;
+use std::task::Wake;
+use std::task::Waker;
+
+use async_trait::async_trait;
+
+use gui::derive::Widget;
+use gui::Handleable;
+use gui::Id;
+use gui::MutCap;
+use gui::Ui;
+use gui::Widget;
+
+use crate::common::Event;
+use crate::common::Message;
+use crate::common::TestWidget;
+use crate::common::TestWidgetDataBuilder;
+
+
+/// The state shared between the two ends of a `channel`.
+#[derive(Debug)]
+struct Shared<T> {
+  value: Option<T>,
+  waker: Option<Waker>,
+}
+
+/// The sending end of a `channel`.
+#[derive(Debug)]
+struct Sender<T>(Rc<RefCell<Shared<T>>>);
+
+impl<T> Sender<T> {
+  fn send(&self, value: T) {
+    let mut shared = self.0.borrow_mut();
+    shared.value = Some(value);
+    if let Some(waker) = shared.waker.take() {
+      waker.wake()
+    }
+  }
+}
+
+/// The receiving end of a `channel`, resolving once a value got sent.
+#[derive(Debug)]
+struct Receiver<T>(Rc<RefCell<Shared<T>>>);
+
+impl<T> Future for Receiver<T> {
+  type Output = T;
+
+  fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+    let mut shared = self.0.borrow_mut();
+    match shared.value.take() {
+      Some(value) => Poll::Ready(value),
+      None => {
+        shared.waker = Some(cx.waker().clone());
+        Poll::Pending
+      },
+    }
+  }
+}
+
+/// Create a single-use channel for sending a value to a future.
+fn channel<T>() -> (Sender<T>, Receiver<T>) {
+  let shared = Rc::new(RefCell::new(Shared {
+    value: None,
+    waker: None,
+  }));
+  (Sender(Rc::clone(&shared)), Receiver(shared))
+}
+
+
+/// A log of message values received by widgets.
+type MessageLog = Rc<RefCell<Vec<u64>>>;
+
+/// Create a function creating widget data for a widget recording the
+/// values of the messages it receives in the provided log.
+fn recording_data(log: &MessageLog) -> impl FnOnce() -> Box<dyn Any> {
+  let log = Rc::clone(log);
+  move || {
+    TestWidgetDataBuilder::new()
+      .react_handler(move |_id, message, _cap| {
+        let () = log.borrow_mut().push(message.value);
+        Box::pin(async { None })
+      })
+      .build()
+  }
+}
+
+/// A log of focus notifications received by widgets.
+type FocusLog = Rc<RefCell<Vec<(Id, bool)>>>;
+
+/// A widget focusing itself when receiving a message.
+#[derive(Debug, Widget)]
+#[gui(Event = Event, Message = Message)]
+struct FocusWidget {
+  id: Id,
+}
+
+#[async_trait(?Send)]
+impl Handleable<Event, Message> for FocusWidget {
+  async fn react(&self, _message: Message, cap: &mut dyn MutCap<Event, Message>) -> Option<Message> {
+    let () = cap.focus(self.id);
+    None
+  }
+
+  async fn focus_changed(&self, cap: &mut dyn MutCap<Event, Message>, gained: bool) {
+    let log = self.data::<FocusLog>(cap);
+    let () = log.borrow_mut().push((self.id, gained));
+  }
+}
+
+
+/// Create a `Ui` with a root and a widget recording the messages it
+/// receives.
+fn setup_ui() -> (Ui<Event, Message>, [Id; 2], MessageLog) {
+  let log = MessageLog::default();
+  let new_widget = |id, _cap: &mut dyn MutCap<Event, Message>| {
+    Box::new(TestWidget::new(id)) as Box<dyn Widget<Event, Message>>
+  };
+  let (mut ui, r) = Ui::new(|| TestWidgetDataBuilder::new().build(), new_widget);
+  let w = ui.add_ui_widget(r, recording_data(&log), new_widget);
+  (ui, [r, w], log)
+}
+
+
+/// Check that the output of a task is delivered to its widget once
+/// the task completed.
+#[tokio::test]
+async fn poll_tasks() {
+  let (mut ui, [_r, w], log) = setup_ui();
+  let (sender, receiver) = channel::<u64>();
+
+  let _task = ui.spawn(
+    w,
+    Box::pin(async move {
+      let value = receiver.await;
+      Message::new(value + 1)
+    }),
+  );
+  let _task = ui.spawn(w, Box::pin(async { Message::new(1) }));
+
+  ui.poll_tasks().await;
+  assert_eq!(log.take(), vec![1]);
+
+  // Tasks that were not woken are not polled.
+  ui.poll_tasks().await;
+  assert_eq!(log.take(), vec![]);
+
+  sender.send(41);
+  ui.poll_tasks().await;
+  assert_eq!(log.take(), vec![42]);
+}
+
+/// Check that tasks get cancelled as expected.
+#[tokio::test]
+async fn cancel_task() {
+  let (mut ui, [_r, w], log) = setup_ui();
+  let (sender1, receiver1) = channel::<u64>();
+  let (sender2, receiver2) = channel::<u64>();
+
+  let task = ui.spawn(w, Box::pin(async move { Message::new(receiver1.await) }));
+  let _task = ui.spawn(w, Box::pin(async move { Message::new(receiver2.await) }));
+  ui.poll_tasks().await;
+
+  assert!(ui.cancel_task(task));
+  assert!(!ui.cancel_task(task));
+  // The task's future got dropped along with the receiver.
+  assert_eq!(Rc::strong_count(&sender1.0), 1);
+
+  // Tasks are cancelled along with their widget.
+  ui.remove_widget(w);
+  assert_eq!(Rc::strong_count(&sender2.0), 1);
+
+  sender2.send(1);
+  ui.poll_tasks().await;
+  assert_eq!(log.take(), vec![]);
+}
+
+/// Check that `Ui::run_until` drives tasks while awaiting the provided
+/// future.
+#[tokio::test]
+async fn run_until() {
+  let log = MessageLog::default();
+  let (result_sender, result_receiver) = channel::<u64>();
+  let new_data = {
+    let log = Rc::clone(&log);
+    move || {
+      TestWidgetDataBuilder::new()
+        .react_handler(move |_id, message, _cap| {
+          let () = log.borrow_mut().push(message.value);
+          let () = result_sender.send(message.value * 2);
+          Box::pin(async { None })
+        })
+        .build()
+    }
+  };
+  let new_widget = |id, _cap: &mut dyn MutCap<Event, Message>| {
+    Box::new(TestWidget::new(id)) as Box<dyn Widget<Event, Message>>
+  };
+  let (mut ui, r) = Ui::new(|| TestWidgetDataBuilder::new().build(), new_widget);
+  let w = ui.add_ui_widget(r, new_data, new_widget);
+
+  let (sender, receiver) = channel::<u64>();
+  let _task = ui.spawn(w, Box::pin(async move { Message::new(receiver.await) }));
+
+  // The future we await only completes once the task completed and
+  // its output got delivered.
+  let result = ui
+    .run_until(async move {
+      sender.send(21);
+      result_receiver.await
+    })
+    .await;
+  assert_eq!(result, 42);
+  assert_eq!(log.take(), vec![21]);
+}
+
+/// Check that focus changes made in response to the output of a task
+/// are notified before `Ui::poll_tasks` and `Ui::run_until` return.
+#[tokio::test]
+async fn task_focus_change() {
+  let log = FocusLog::default();
+  let new_data = || {
+    let log = Rc::clone(&log);
+    move || Box::new(log) as Box<dyn Any>
+  };
+  let new_widget = |id, _cap: &mut dyn MutCap<Event, Message>| {
+    Box::new(FocusWidget { id }) as Box<dyn Widget<Event, Message>>
+  };
+  let (mut ui, r) = Ui::new(new_data(), new_widget);
+  let w1 = ui.add_ui_widget(r, new_data(), new_widget);
+  let w2 = ui.add_ui_widget(r, new_data(), new_widget);
+
+  let _task = ui.spawn(w1, Box::pin(async { Message::new(1) }));
+  ui.poll_tasks().await;
+  assert_eq!(ui.focused(), Some(w1));
+  assert_eq!(log.take(), vec![(w1, true)]);
+
+  let (sender, receiver) = channel::<u64>();
+  let _task = ui.spawn(w2, Box::pin(async move { Message::new(receiver.await) }));
+  let mut sent = false;
+  // Wake the task and complete only once polled again, which happens
+  // after the task got polled.
+  let () = ui
+    .run_until(poll_fn(move |_cx| {
+      if sent {
+        return Poll::Ready(())
+      }
+      let () = sender.send(2);
+      sent = true;
+      Poll::Pending
+    }))
+    .await;
+  assert_eq!(ui.focused(), Some(w2));
+  assert_eq!(log.take(), vec![(w1, false), (w2, true)]);
+}
+
+/// A waker waking another waker, if set, when woken itself.
+#[derive(Debug, Default)]
+struct ChainWaker(Mutex<Option<Waker>>);
+
+impl Wake for ChainWaker {
+  fn wake(self: Arc<Self>) {
+    let waker = self.0.lock().unwrap().take();
+    if let Some(waker) = waker {
+      waker.wake()
+    }
+  }
+}
+
+/// Check that a waker driving `Ui::run_until` may wake tasks
+/// synchronously.
+#[test]
+fn run_until_wake_from_driver() {
+  let (mut ui, [_r, w], _log) = setup_ui();
+  let task_waker = Arc::new(Mutex::new(None::<Waker>));
+  let _task = ui.spawn(w, {
+    let task_waker = Arc::clone(&task_waker);
+    Box::pin(poll_fn(move |cx| {
+      *task_waker.lock().unwrap() = Some(cx.waker().clone());
+      Poll::Pending
+    }))
+  });
+
+  let driver = Arc::new(ChainWaker::default());
+  let waker = Waker::from(Arc::clone(&driver));
+  let mut cx = Context::from_waker(&waker);
+  let mut future = Box::pin(ui.run_until(pending::<()>()));
+  assert!(future.as_mut().poll(&mut cx).is_pending());
+
+  // Once the task gets woken, the driver wakes it yet again, while
+  // the task's waker is still being invoked.
+  let task_waker = task_waker.lock().unwrap().take().unwrap();
+  *driver.0.lock().unwrap() = Some(task_waker.clone());
+  let () = task_waker.wake();
+  assert!(driver.0.lock().unwrap().is_none());
+  assert!(future.as_mut().poll(&mut cx).is_pending());
+}
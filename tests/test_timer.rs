@@ -0,0 +1,264 @@
+// Copyright (C) 2026 Daniel Mueller <deso@posteo.net>
+// SPDX-License-Identifier: GPL-3.0-or-later
+
+//! Tests for timer related functionality.
+
+mod common;
+
+use std::any::Any;
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+use std::time::Instant;
+
+use async_trait::async_trait;
+
+use gui::derive::Widget;
+use gui::Error;
+use gui::Handleable;
+use gui::Id;
+use gui::MutCap;
+use gui::TimerId;
+use gui::Ui;
+use gui::Widget;
+
+use crate::common::Event;
+use crate::common::Message;
+
+
+/// A log of timers that fired.
+type TimerLog = Rc<RefCell<Vec<TimerId>>>;
+
+/// The data of a `TimerWidget`.
+#[derive(Debug)]
+struct TimerData {
+  /// A timer to cancel once a timer of the widget fires.
+  cancel: Option<TimerId>,
+  /// A widget to focus once a timer of the widget fires.
+  focus: Option<Id>,
+  /// The focus notifications the widget received.
+  focus_changes: Vec<bool>,
+  /// The log to record fired timers in.
+  log: TimerLog,
+}
+
+/// A widget recording the timers firing for it.
+#[derive(Debug, Widget)]
+#[gui(Event = Event, Message = Message)]
+struct TimerWidget {
+  id: Id,
+}
+
+#[async_trait(?Send)]
+impl Handleable<Event, Message> for TimerWidget {
+  async fn timer_fired(&self, cap: &mut dyn MutCap<Event, Message>, timer: TimerId) {
+    let data = self.data::<TimerData>(cap);
+    let () = data.log.borrow_mut().push(timer);
+    let (cancel, focus) = (data.cancel, data.focus);
+
+    if let Some(cancel) = cancel {
+      let _cancelled = cap.cancel_timer(cancel);
+    }
+
+    if let Some(focus) = focus {
+      let () = cap.focus(focus);
+    }
+  }
+
+  async fn focus_changed(&self, cap: &mut dyn MutCap<Event, Message>, gained: bool) {
+    let () = self.data_mut::<TimerData>(cap).focus_changes.push(gained);
+  }
+}
+
+
+/// Create a `Ui` with a root and two widgets, along with a clock
+/// controlling the current time of the `Ui`.
+fn setup_ui() -> (Ui<Event, Message>, [Id; 3], Rc<Cell<Instant>>, TimerLog) {
+  let log = TimerLog::default();
+  let new_data = || {
+    let log = Rc::clone(&log);
+    move || {
+      Box::new(TimerData {
+        cancel: None,
+        focus: None,
+        focus_changes: Vec::new(),
+        log,
+      }) as Box<dyn Any>
+    }
+  };
+  let new_widget = |id, _cap: &mut dyn MutCap<Event, Message>| {
+    Box::new(TimerWidget { id }) as Box<dyn Widget<Event, Message>>
+  };
+
+  let (mut ui, r) = Ui::new(new_data(), new_widget);
+  let w1 = ui.add_ui_widget(r, new_data(), new_widget);
+  let w2 = ui.add_ui_widget(r, new_data(), new_widget);
+
+  let clock = Rc::new(Cell::new(Instant::now()));
+  let () = ui.set_clock({
+    let clock = Rc::clone(&clock);
+    move || clock.get()
+  });
+
+  (ui, [r, w1, w2], clock, log)
+}
+
+
+/// Check that timers fire in order of their deadlines.
+#[tokio::test]
+async fn fire_timers() {
+  let (mut ui, [_r, w1, w2], clock, log) = setup_ui();
+  let start = clock.get();
+  assert_eq!(ui.next_deadline(), None);
+
+  let t1 = ui.set_timer(w1, Duration::from_secs(3), false).unwrap();
+  let t2 = ui.set_timer(w2, Duration::from_secs(2), false).unwrap();
+  let t3 = ui.set_timer(w1, Duration::from_secs(5), false).unwrap();
+  assert_eq!(ui.next_deadline(), Some(start + Duration::from_secs(2)));
+
+  ui.fire_timers(start + Duration::from_secs(1)).await;
+  assert_eq!(log.take(), vec![]);
+
+  ui.fire_timers(start + Duration::from_secs(3)).await;
+  assert_eq!(log.take(), vec![t2, t1]);
+  assert_eq!(ui.next_deadline(), Some(start + Duration::from_secs(5)));
+
+  // Timers fire only once.
+  ui.fire_timers(start + Duration::from_secs(4)).await;
+  assert_eq!(log.take(), vec![]);
+
+  ui.fire_timers(start + Duration::from_secs(5)).await;
+  assert_eq!(log.take(), vec![t3]);
+  assert_eq!(ui.next_deadline(), None);
+}
+
+/// Check that repeating timers keep firing.
+#[tokio::test]
+async fn repeat_timer() {
+  let (mut ui, [_r, w1, _w2], clock, log) = setup_ui();
+  let start = clock.get();
+
+  clock.set(start + Duration::from_secs(1));
+  let timer = ui.set_timer(w1, Duration::from_secs(2), true).unwrap();
+  assert_eq!(ui.next_deadline(), Some(start + Duration::from_secs(3)));
+
+  ui.fire_timers(start + Duration::from_secs(3)).await;
+  assert_eq!(log.take(), vec![timer]);
+  assert_eq!(ui.next_deadline(), Some(start + Duration::from_secs(5)));
+
+  // Missed expirations are coalesced.
+  ui.fire_timers(start + Duration::from_secs(10)).await;
+  assert_eq!(log.take(), vec![timer]);
+  assert_eq!(ui.next_deadline(), Some(start + Duration::from_secs(12)));
+
+  assert!(ui.cancel_timer(timer));
+  assert!(!ui.cancel_timer(timer));
+  assert_eq!(ui.next_deadline(), None);
+
+  ui.fire_timers(start + Duration::from_secs(20)).await;
+  assert_eq!(log.take(), vec![]);
+}
+
+/// Check that timers get cancelled as expected.
+#[tokio::test]
+async fn cancel_timer() {
+  let (mut ui, [_r, w1, w2], clock, log) = setup_ui();
+  let start = clock.get();
+
+  let t1 = ui.set_timer(w1, Duration::from_secs(1), false).unwrap();
+  let t2 = ui.set_timer(w2, Duration::from_secs(1), true).unwrap();
+  let t3 = ui.set_timer(w2, Duration::from_secs(2), false).unwrap();
+
+  // A timer cancelled by a handler does not fire anymore, even if it
+  // expired already.
+  let data = ui.data_mut(w1).downcast_mut::<TimerData>().unwrap();
+  data.cancel = Some(t2);
+
+  ui.fire_timers(start + Duration::from_secs(2)).await;
+  assert_eq!(log.take(), vec![t1, t3]);
+  assert!(!ui.cancel_timer(t1));
+  assert!(!ui.cancel_timer(t2));
+
+  // Timers are cancelled along with their widget.
+  let t4 = ui.set_timer(w2, Duration::from_secs(1), false).unwrap();
+  let _t5 = ui.set_timer(w2, Duration::from_secs(1), true).unwrap();
+  assert_ne!(ui.next_deadline(), None);
+
+  ui.remove_widget(w2);
+  assert_eq!(ui.next_deadline(), None);
+  assert!(!ui.cancel_timer(t4));
+
+  ui.fire_timers(start + Duration::from_secs(10)).await;
+  assert_eq!(log.take(), vec![]);
+
+  // Timers cannot be set for removed widgets.
+  let result = ui.set_timer(w2, Duration::from_secs(1), false);
+  assert_eq!(result, Err(Error::InvalidId));
+}
+
+/// Check that repeating timers with a zero interval are rejected.
+#[tokio::test]
+async fn zero_interval_timer() {
+  let (mut ui, [_r, w1, _w2], clock, log) = setup_ui();
+  let start = clock.get();
+
+  let result = ui.set_timer(w1, Duration::ZERO, true);
+  assert_eq!(result, Err(Error::ZeroInterval));
+  assert_eq!(ui.next_deadline(), None);
+
+  // One-shot timers may expire right away.
+  let timer = ui.set_timer(w1, Duration::ZERO, false).unwrap();
+  assert_eq!(ui.next_deadline(), Some(start));
+
+  ui.fire_timers(start).await;
+  assert_eq!(log.take(), vec![timer]);
+  assert_eq!(ui.next_deadline(), None);
+}
+
+/// Check that timers with deadlines too far in the future never fire.
+#[tokio::test]
+async fn unrepresentable_deadline_timer() {
+  let (mut ui, [_r, w1, w2], clock, log) = setup_ui();
+  let start = clock.get();
+
+  let t1 = ui.set_timer(w1, Duration::MAX, false).unwrap();
+  let _t2 = ui.set_timer(w2, Duration::MAX, true).unwrap();
+  assert_eq!(ui.next_deadline(), None);
+
+  ui.fire_timers(start + Duration::from_secs(1000)).await;
+  assert_eq!(log.take(), vec![]);
+  assert!(ui.cancel_timer(t1));
+
+  // A repeating timer whose next deadline cannot be represented stops
+  // firing.
+  let interval = Duration::MAX / 2;
+  let t3 = ui.set_timer(w1, interval, true).unwrap();
+  let expected = start.checked_add(interval);
+  assert_eq!(ui.next_deadline(), expected);
+
+  if let Some(deadline) = expected {
+    ui.fire_timers(deadline).await;
+    assert_eq!(log.take(), vec![t3]);
+    assert_eq!(ui.next_deadline(), None);
+  }
+}
+
+/// Check that focus changes made by a timer handler are notified
+/// before `Ui::fire_timers` returns.
+#[tokio::test]
+async fn timer_focus_change() {
+  let (mut ui, [_r, w1, w2], clock, log) = setup_ui();
+  let start = clock.get();
+
+  let data = ui.data_mut(w1).downcast_mut::<TimerData>().unwrap();
+  data.focus = Some(w2);
+
+  let timer = ui.set_timer(w1, Duration::from_secs(1), false).unwrap();
+  ui.fire_timers(start + Duration::from_secs(1)).await;
+  assert_eq!(log.take(), vec![timer]);
+  assert_eq!(ui.focused(), Some(w2));
+
+  let data = ui.data(w2).downcast_ref::<TimerData>().unwrap();
+  assert_eq!(data.focus_changes, vec![true]);
+}
@@ -5,19 +5,23 @@
 
 mod common;
 
+use std::any::TypeId;
 use std::fmt::Write;
 use std::future::Future;
 use std::pin::Pin;
+use std::rc::Rc;
 
 use async_trait::async_trait;
 
 use gui::derive::Handleable;
 use gui::derive::Widget;
 use gui::Cap;
+use gui::Error;
 use gui::Handleable;
 use gui::Id;
 use gui::MutCap;
 use gui::Ui;
+use gui::Widget;
 
 use crate::common::Event;
 use crate::common::Message;
@@ -163,7 +167,6 @@ async fn incorrect_widget_id() {
 }
 
 #[tokio::test]
-#[cfg(debug_assertions)]
 #[should_panic(expected = "The given Id belongs to a different Ui")]
 async fn share_ids_between_ui_objects() {
   let (mut ui1, root) = Ui::new(
@@ -182,8 +185,8 @@ async fn share_ids_between_ui_objects() {
   );
 
   // `widget` is registered to `ui1` and so using it in the context of
-  // `ui2` is not as intended. On debug builds we have special detection
-  // in place to provide a meaningful error, that should trigger here.
+  // `ui2` is not as intended. We have special detection in place to
+  // provide a meaningful error, that should trigger here.
   let message = Message::new(0);
   ui2.send(widget, message).await;
 }
@@ -627,3 +630,466 @@ async fn recursive_widget_operations() {
   let result = ui.handle(Event::Empty).await.unwrap();
   assert_eq!(result.unwrap_int(), 42);
 }
+
+
+/// Check that removing a widget detaches it and its children from the
+/// `Ui`.
+#[test]
+fn remove_widget() {
+  let (mut ui, root) = Ui::new(
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w1 = ui.add_ui_widget(
+    root,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w11 = ui.add_ui_widget(
+    w1,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w2 = ui.add_ui_widget(
+    root,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+
+  ui.focus(w11);
+  assert!(ui.is_focused(w11));
+
+  ui.remove_widget(w1);
+  assert!(ui.focused().is_none());
+
+  let mut it = ui.children(root);
+  assert_eq!(*it.next().unwrap(), w2);
+  assert!(it.next().is_none());
+}
+
+/// Check that the data of removed widgets gets dropped.
+#[test]
+fn remove_widget_drops_data() {
+  let data = Rc::new(());
+  let (mut ui, root) = Ui::new(
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let data_clone = Rc::clone(&data);
+  let w1 = ui.add_ui_widget(
+    root,
+    move || Box::new(data_clone),
+    |id, _cap| Box::new(MovingWidget::new(id)),
+  );
+  let data_clone = Rc::clone(&data);
+  let _ = ui.add_ui_widget(
+    w1,
+    move || Box::new(data_clone),
+    |id, _cap| Box::new(MovingWidget::new(id)),
+  );
+  assert_eq!(Rc::strong_count(&data), 3);
+
+  ui.remove_widget(w1);
+  assert_eq!(Rc::strong_count(&data), 1);
+}
+
+/// Check that the root widget cannot be removed.
+#[test]
+#[should_panic(expected = "The root widget cannot be removed")]
+fn remove_root_widget() {
+  let (mut ui, root) = Ui::new(
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  ui.remove_widget(root);
+}
+
+
+fn removing_handler(
+  widget: Id,
+  cap: &mut dyn MutCap<Event, Message>,
+  _event: Event,
+) -> Pin<Box<dyn Future<Output = Option<Event>> + '_>> {
+  Box::pin(async move {
+    cap.remove_widget(widget);
+    Some(Event::Int(1))
+  })
+}
+
+/// Check that a widget can remove itself while handling an event.
+#[tokio::test]
+async fn remove_widget_from_handler() {
+  let (mut ui, root) = Ui::new(
+    || {
+      TestWidgetDataBuilder::new()
+        .event_handler(counting_handler)
+        .build()
+    },
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let widget = ui.add_ui_widget(
+    root,
+    || {
+      TestWidgetDataBuilder::new()
+        .event_handler(removing_handler)
+        .build()
+    },
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  ui.focus(widget);
+
+  // The event does not bubble up to the root, because the widget
+  // handling it got removed.
+  let result = ui.handle(Event::Empty).await.unwrap();
+  assert_eq!(result.unwrap_int(), 1);
+  assert_eq!(ui.children(root).count(), 0);
+  assert!(ui.focused().is_none());
+}
+
+/// Check that the `Id` of a removed widget is not mistaken for that of
+/// a widget created subsequently.
+#[test]
+#[should_panic(expected = "The given Id refers to a removed widget")]
+fn stale_widget_id() {
+  let (mut ui, root) = Ui::new(
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w1 = ui.add_ui_widget(
+    root,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  ui.remove_widget(w1);
+
+  let w2 = ui.add_ui_widget(
+    root,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  // The new widget reuses the storage of the removed one, but it does
+  // not share its `Id`.
+  assert_eq!(w1.to_string(), w2.to_string());
+  assert_ne!(w1, w2);
+  assert_eq!(ui.parent_id(w2), Some(root));
+
+  let _ = ui.parent_id(w1);
+}
+
+
+/// Check that the fallible accessors report invalid `Id`s.
+#[tokio::test]
+async fn fallible_accessors() {
+  let (mut ui, root) = Ui::new(
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let widget = ui.add_ui_widget(
+    root,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+
+  assert_eq!(ui.try_parent_id(widget), Ok(Some(root)));
+  assert!(ui.try_data(widget).is_ok());
+  assert_eq!(ui.try_children(root).unwrap().count(), 1);
+
+  ui.remove_widget(widget);
+
+  assert_eq!(ui.try_parent_id(widget), Err(Error::InvalidId));
+  assert_eq!(ui.try_data(widget).err(), Some(Error::InvalidId));
+  assert_eq!(ui.try_data_mut(widget).err(), Some(Error::InvalidId));
+  assert_eq!(ui.try_children(widget).err(), Some(Error::InvalidId));
+
+  let result = ui.try_send(widget, Message::new(1)).await;
+  assert_eq!(result, Err(Error::InvalidId));
+  let result = ui.try_call(widget, &mut Message::new(1)).await;
+  assert_eq!(result, Err(Error::InvalidId));
+}
+
+/// Check that usage of an `Id` belonging to a different `Ui` is
+/// reported.
+#[test]
+fn fallible_accessors_foreign_ui() {
+  let (ui1, _) = Ui::new(
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let (ui2, root) = Ui::new(
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+
+  assert!(ui2.try_parent_id(root).is_ok());
+  assert_eq!(ui1.try_parent_id(root), Err(Error::ForeignUi));
+}
+
+
+#[derive(Debug, Widget)]
+#[gui(Event = Event, Message = Message)]
+struct TypedDataWidget {
+  id: Id,
+}
+
+#[async_trait(?Send)]
+impl Handleable<Event, Message> for TypedDataWidget {
+  async fn handle(&self, cap: &mut dyn MutCap<Event, Message>, event: Event) -> Option<Event> {
+    assert_eq!(self.try_data::<u64>(cap), Ok(&42));
+    assert_eq!(
+      self.try_data::<String>(cap),
+      Err(Error::DataTypeMismatch {
+        expected: TypeId::of::<String>(),
+        actual: TypeId::of::<u64>(),
+      })
+    );
+
+    *self.try_data_mut::<u64>(cap).unwrap() += 1;
+    assert!(self.try_data_mut::<()>(cap).is_err());
+    Some(event)
+  }
+}
+
+/// Check that `Widget::try_data` and `Widget::try_data_mut` report
+/// data type mismatches.
+#[tokio::test]
+async fn fallible_data_access() {
+  let (mut ui, root) = Ui::new(
+    || Box::new(42u64),
+    |id, _cap| Box::new(TypedDataWidget { id }),
+  );
+  ui.focus(root);
+
+  let _ = ui.handle(Event::Empty).await;
+  assert_eq!(ui.data(root).downcast_ref::<u64>(), Some(&43));
+}
+
+
+/// Check that we can move a widget to a different parent.
+#[test]
+fn reparent_widget() {
+  let (mut ui, root) = Ui::new(
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let c1 = ui.add_ui_widget(
+    root,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let c2 = ui.add_ui_widget(
+    root,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w1 = ui.add_ui_widget(
+    c1,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w2 = ui.add_ui_widget(
+    c2,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w3 = ui.add_ui_widget(
+    c2,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+
+  ui.set_parent(w1, c2, 1).unwrap();
+  assert_eq!(ui.parent_id(w1), Some(c2));
+  assert_eq!(ui.children(c1).count(), 0);
+
+  let children = ui.children(c2).cloned().collect::<Vec<_>>();
+  assert_eq!(children, vec![w2, w1, w3]);
+
+  // Positions past the end place the widget at the bottom.
+  ui.set_parent(w2, c1, 42).unwrap();
+  ui.set_parent(c2, c1, 42).unwrap();
+  let children = ui.children(c1).cloned().collect::<Vec<_>>();
+  assert_eq!(children, vec![w2, c2]);
+  assert_eq!(ui.parent_id(c2), Some(c1));
+}
+
+/// Check that moving a widget below itself is rejected.
+#[test]
+fn reparent_widget_cycle() {
+  let (mut ui, root) = Ui::new(
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let c1 = ui.add_ui_widget(
+    root,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w1 = ui.add_ui_widget(
+    c1,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+
+  assert_eq!(ui.set_parent(c1, c1, 0), Err(Error::Cycle));
+  assert_eq!(ui.set_parent(c1, w1, 0), Err(Error::Cycle));
+  assert_eq!(ui.set_parent(root, w1, 0), Err(Error::Cycle));
+  assert_eq!(ui.parent_id(c1), Some(root));
+  assert_eq!(ui.parent_id(w1), Some(c1));
+}
+
+/// Check that reparenting a widget maintains focus invariants.
+#[test]
+fn reparent_focused_widget() {
+  let (mut ui, root) = Ui::new(
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let c1 = ui.add_ui_widget(
+    root,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let c2 = ui.add_ui_widget(
+    root,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w1 = ui.add_ui_widget(
+    c2,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w2 = ui.add_ui_widget(
+    c1,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+
+  ui.focus(w2);
+
+  // The focused widget stays focused and on top when moved to a
+  // displayed parent.
+  ui.set_parent(w2, c2, 1).unwrap();
+  assert!(ui.is_focused(w2));
+  assert_eq!(*ui.children(c2).next().unwrap(), w2);
+  assert_eq!(*ui.children(root).next().unwrap(), c2);
+
+  // When moved to a hidden parent, it loses the focus.
+  ui.hide(c1);
+  ui.set_parent(w2, c1, 0).unwrap();
+  assert!(ui.focused().is_none());
+  assert_eq!(*ui.children(c2).next().unwrap(), w1);
+}
+
+
+/// Check that the z-order of widgets can be adjusted explicitly.
+#[test]
+fn explicit_z_order() {
+  let (mut ui, root) = Ui::new(
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w1 = ui.add_ui_widget(
+    root,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w2 = ui.add_ui_widget(
+    root,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w3 = ui.add_ui_widget(
+    root,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w11 = ui.add_ui_widget(
+    w1,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+
+  ui.raise(w3);
+  let children = ui.children(root).cloned().collect::<Vec<_>>();
+  assert_eq!(children, vec![w3, w1, w2]);
+
+  ui.lower(w3);
+  let children = ui.children(root).cloned().collect::<Vec<_>>();
+  assert_eq!(children, vec![w1, w2, w3]);
+
+  ui.raise_above(w3, w2).unwrap();
+  let children = ui.children(root).cloned().collect::<Vec<_>>();
+  assert_eq!(children, vec![w1, w3, w2]);
+
+  ui.raise_above(w1, w2).unwrap();
+  let children = ui.children(root).cloned().collect::<Vec<_>>();
+  assert_eq!(children, vec![w3, w1, w2]);
+
+  ui.raise_above(w2, w2).unwrap();
+  let children = ui.children(root).cloned().collect::<Vec<_>>();
+  assert_eq!(children, vec![w3, w1, w2]);
+
+  assert_eq!(ui.raise_above(w11, w2), Err(Error::NotSiblings));
+}
+
+/// Check that a widget can be raised above the focused one.
+#[test]
+fn raise_above_focused() {
+  let (mut ui, root) = Ui::new(
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w1 = ui.add_ui_widget(
+    root,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w2 = ui.add_ui_widget(
+    root,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+
+  ui.focus(w1);
+  ui.raise(w2);
+  assert!(ui.is_focused(w1));
+
+  let children = ui.children(root).cloned().collect::<Vec<_>>();
+  assert_eq!(children, vec![w2, w1]);
+}
+
+/// Check that widgets can be created at a given z-order position.
+#[test]
+fn add_widget_at_position() {
+  let (mut ui, root) = Ui::new(
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w1 = ui.add_ui_widget(
+    root,
+    || TestWidgetDataBuilder::new().build(),
+    |id, _cap| Box::new(TestWidget::new(id)),
+  );
+  let w2 = ui.add_widget_at(
+    root,
+    0,
+    Box::new(|| TestWidgetDataBuilder::new().build()),
+    Box::new(|id, _cap| Box::new(TestWidget::new(id))),
+  );
+  let w3 = ui.add_widget_at(
+    root,
+    1,
+    Box::new(|| TestWidgetDataBuilder::new().build()),
+    Box::new(|id, _cap| Box::new(TestWidget::new(id))),
+  );
+  let w4 = ui.add_widget_at(
+    root,
+    usize::MAX,
+    Box::new(|| TestWidgetDataBuilder::new().build()),
+    Box::new(|id, _cap| Box::new(TestWidget::new(id))),
+  );
+
+  let children = ui.children(root).cloned().collect::<Vec<_>>();
+  assert_eq!(children, vec![w2, w3, w1, w4]);
+}